@@ -0,0 +1,114 @@
+use rand::Rng;
+
+use super::get_draw_position;
+use crate::game::{Game, GameEvent, Position, GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH};
+
+const MIN_ZOOM: f64 = 1.0;
+const MAX_ZOOM: f64 = 4.0;
+// Each zoom step multiplies or divides the zoom by this
+const ZOOM_STEP: f64 = 1.25;
+// How quickly the zoom closes on its target, as a fraction of the gap per second
+const ZOOM_RATE: f64 = 8.0;
+// In arena units of shake
+const HIT_SHAKE: f64 = 8.0;
+const BOMB_SHAKE: f64 = 5.0;
+const BOSS_STARTED_SHAKE: f64 = 6.0;
+// Per phase, so each phase arrives harder than the last
+const BOSS_PHASE_SHAKE: f64 = 2.0;
+const BOSS_DEFEATED_SHAKE: f64 = 10.0;
+// Shake dies away by this fraction of itself per second
+const SHAKE_DECAY: f64 = 6.0;
+// Arena units per second
+const FREE_CAMERA_SPEED: f64 = 300.0;
+
+/* Turns arena positions into positions to draw at. The world is drawn with the view's zoom applied as a
+render scale, so only positions need transforming and sizes are drawn as they are. */
+pub struct Camera {
+    // The arena point at the centre of the view
+    pub x: f64,
+    pub y: f64,
+    pub zoom: f64,
+    target_zoom: f64,
+    shake: f64,
+    shake_x: f64,
+    shake_y: f64,
+    // Panned by hand for debugging, instead of keeping the player in view
+    pub free: bool,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera {
+            x: GAME_SCREEN_WIDTH / 2.0,
+            y: GAME_SCREEN_HEIGHT / 2.0,
+            zoom: 1.0,
+            target_zoom: 1.0,
+            shake: 0.0,
+            shake_x: 0.0,
+            shake_y: 0.0,
+            free: false,
+        }
+    }
+    pub fn to_screen(&self, position: &Position) -> (i16, i16) {
+        (
+            (position.x - self.x + self.shake_x + GAME_SCREEN_WIDTH / (2.0 * self.zoom)) as i16,
+            (position.y - self.y + self.shake_y + GAME_SCREEN_HEIGHT / (2.0 * self.zoom)) as i16,
+        )
+    }
+    // From arena-sized view coordinates, like a mouse position out of the viewport, ignoring shake
+    pub fn view_to_world(&self, position: &Position) -> Position {
+        Position {
+            x: (position.x - GAME_SCREEN_WIDTH / 2.0) / self.zoom + self.x,
+            y: (position.y - GAME_SCREEN_HEIGHT / 2.0) / self.zoom + self.y,
+        }
+    }
+    pub fn zoom_in(&mut self) {
+        self.target_zoom = (self.target_zoom * ZOOM_STEP).min(MAX_ZOOM);
+    }
+    pub fn zoom_out(&mut self) {
+        self.target_zoom = (self.target_zoom / ZOOM_STEP).max(MIN_ZOOM);
+    }
+    pub fn toggle_free(&mut self) {
+        self.free = !self.free;
+    }
+    pub fn pan(&mut self, dx: f64, dy: f64, seconds: f64) {
+        self.x += dx * FREE_CAMERA_SPEED * seconds;
+        self.y += dy * FREE_CAMERA_SPEED * seconds;
+    }
+    // Called with each tick's events, before they're cleared by the next step
+    pub fn handle_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            let shake = match event {
+                GameEvent::PlayerHit => HIT_SHAKE,
+                GameEvent::BombDetonated => BOMB_SHAKE,
+                GameEvent::BossStarted => BOSS_STARTED_SHAKE,
+                GameEvent::BossPhaseChanged(phase) => BOSS_PHASE_SHAKE * *phase as f64,
+                GameEvent::BossDefeated => BOSS_DEFEATED_SHAKE,
+                GameEvent::PickupSpawned(_) | GameEvent::PickupCollected(_) => continue,
+            };
+            self.shake = self.shake.max(shake);
+        }
+    }
+    // Given the same interpolation the world is drawn with, so the view moves as smoothly as the player does
+    pub fn update(&mut self, game: &Game, interpolation: f64, seconds: f64) {
+        self.zoom += (self.target_zoom - self.zoom) * (1.0 - (-ZOOM_RATE * seconds).exp());
+        self.shake *= (-SHAKE_DECAY * seconds).exp();
+        let mut rng = rand::thread_rng();
+        self.shake_x = rng.gen_range(-1.0..=1.0) * self.shake;
+        self.shake_y = rng.gen_range(-1.0..=1.0) * self.shake;
+        if self.free {
+            return;
+        }
+        // Follow the player, but never show past the arena's edges
+        let (mut x, mut y) = (GAME_SCREEN_WIDTH / 2.0, GAME_SCREEN_HEIGHT / 2.0);
+        if let Some(player) = game.player.as_ref() {
+            if let Some(position) = get_draw_position(game, &player.id, interpolation) {
+                (x, y) = (position.x, position.y);
+            }
+        }
+        let half_width = GAME_SCREEN_WIDTH / (2.0 * self.zoom);
+        let half_height = GAME_SCREEN_HEIGHT / (2.0 * self.zoom);
+        self.x = x.clamp(half_width, GAME_SCREEN_WIDTH - half_width);
+        self.y = y.clamp(half_height, GAME_SCREEN_HEIGHT - half_height);
+    }
+}
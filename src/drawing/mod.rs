@@ -1,5 +1,26 @@
-use crate::game::{Game, DASHER_MOVE_FOR};
+use crate::game::{
+    Boss, DangerShape, Dasher, Game, GameObjectId, Mine, MineState, PickupKind, Position,
+    SniperState, Splitter, Zone, BOMB_BLAST_DURATION, BOSS_PHASES, DASHER_RADIUS, DODGE_COOLDOWN,
+    GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH, LASER_LENGTH, MAX_GRAZE_METER, MINE_ARM_TIME,
+    MINE_BLAST_RADIUS, MINE_TRIGGER_RADIUS, PLAYER_DANGER_RADIUS, SPARK_DURATION,
+};
+use std::collections::HashMap;
+
+mod camera;
+pub use camera::*;
+
+mod viewport;
+pub use viewport::*;
+
+mod offscreen;
+pub use offscreen::*;
+
+mod notice;
+pub use notice::*;
+
+use crate::input::{Bindings, ACTIONS};
 use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::keyboard::Keycode;
 use sdl2::render::{Canvas, RenderTarget};
 
 const DASHER_VISUAL_RADIUS: i16 = 5;
@@ -7,12 +28,19 @@ const PLAYER_VISUAL_RADIUS: i16 = 5;
 const SIN_ONE_EIGHTH: f64 = 0.70710678118;
 const LINE_COLOR: (u8, u8, u8, u8) = (0, 0, 0, 255);
 const ARROW_PRONG_LENGTH: f64 = 2.0;
-fn draw_arrow<T: RenderTarget>(canvas: &mut Canvas<T>, x1: i16, y1: i16, x2: i16, y2: i16) {
+fn draw_arrow<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    x1: i16,
+    y1: i16,
+    x2: i16,
+    y2: i16,
+    color: (u8, u8, u8, u8),
+) {
     let dx = x2 - x1;
     let dy = y2 - y1;
     // Draw the main line of the arrow
     canvas
-        .aa_line(x1, y1, x2, y2, (0, 0, 0, 255))
+        .aa_line(x1, y1, x2, y2, color)
         .expect("Could not draw line");
 
     /* Draw the two prongs of the arrow
@@ -29,7 +57,7 @@ fn draw_arrow<T: RenderTarget>(canvas: &mut Canvas<T>, x1: i16, y1: i16, x2: i16
             y2,
             x2 + rx1_normalised as i16,
             y2 + ry1_normalised as i16,
-            LINE_COLOR,
+            color,
         )
         .unwrap();
 
@@ -44,39 +72,735 @@ fn draw_arrow<T: RenderTarget>(canvas: &mut Canvas<T>, x1: i16, y1: i16, x2: i16
             y2,
             x2 + rx2_normalised as i16,
             y2 + ry2_normalised as i16,
-            LINE_COLOR,
+            color,
         )
         .unwrap();
 }
+// Any jump further than this between two points on a path must be wrapping around the screen
+const PATH_WRAP_JUMP: i16 = GAME_SCREEN_WIDTH as i16 / 2;
+// Draws a predicted path, with an arrow head at its end
+fn draw_path<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    path: &[Position],
+    camera: &Camera,
+    color: (u8, u8, u8, u8),
+) {
+    let mut points: Vec<(i16, i16)> = Vec::new();
+    for position in path {
+        let point = camera.to_screen(position);
+        if points.last() != Some(&point) {
+            points.push(point);
+        }
+    }
+    if points.len() < 2 {
+        return;
+    }
+    for pair in points.windows(2) {
+        let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+        if (x2 - x1).abs() > PATH_WRAP_JUMP || (y2 - y1).abs() > PATH_WRAP_JUMP {
+            continue;
+        }
+        canvas
+            .aa_line(x1, y1, x2, y2, color)
+            .expect("Could not draw path");
+    }
+    // Take the arrow's direction from far enough back that rounding doesn't skew it
+    let (end_x, end_y) = points[points.len() - 1];
+    let (start_x, start_y) = points
+        .iter()
+        .rev()
+        .skip(1)
+        .find(|(x, y)| (end_x - x).abs() + (end_y - y).abs() >= ARROW_DIRECTION_DISTANCE)
+        .copied()
+        .unwrap_or(points[points.len() - 2]);
+    if (end_x - start_x).abs() > PATH_WRAP_JUMP || (end_y - start_y).abs() > PATH_WRAP_JUMP {
+        return;
+    }
+    draw_arrow(canvas, start_x, start_y, end_x, end_y, color);
+}
+const ARROW_DIRECTION_DISTANCE: i16 = 4;
+const SPARK_COLOR: (u8, u8, u8, u8) = (255, 200, 0, 255);
+const SPARK_MAX_SIZE: f64 = 8.0;
+fn draw_spark<T: RenderTarget>(canvas: &mut Canvas<T>, x: i16, y: i16, size: i16) {
+    canvas
+        .aa_line(x - size, y, x + size, y, SPARK_COLOR)
+        .expect("Could not draw spark");
+    canvas
+        .aa_line(x, y - size, x, y + size, SPARK_COLOR)
+        .expect("Could not draw spark");
+    let diagonal = size / 2;
+    canvas
+        .aa_line(
+            x - diagonal,
+            y - diagonal,
+            x + diagonal,
+            y + diagonal,
+            SPARK_COLOR,
+        )
+        .expect("Could not draw spark");
+    canvas
+        .aa_line(
+            x - diagonal,
+            y + diagonal,
+            x + diagonal,
+            y - diagonal,
+            SPARK_COLOR,
+        )
+        .expect("Could not draw spark");
+}
+const METER_HEIGHT: i16 = 4;
+const METER_COLOR: (u8, u8, u8, u8) = (255, 200, 0, 255);
+const DODGE_COOLDOWN_COLOR: (u8, u8, u8, u8) = (0, 200, 255, 255);
+fn draw_meter<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    value: u32,
+    max: u32,
+    y: i16,
+    color: (u8, u8, u8, u8),
+) {
+    let width = (GAME_SCREEN_WIDTH * value as f64 / max as f64) as i16;
+    if width > 0 {
+        canvas
+            .box_(0, y, width, y + METER_HEIGHT, color)
+            .expect("Could not draw meter");
+    }
+}
+const HUNTER_COLOR: (u8, u8, u8, u8) = (255, 120, 0, 255);
+const HUNTER_AIM_COLOR: (u8, u8, u8, u8) = (255, 120, 0, 120);
+const HUNTER_AIM_SIZE: i16 = 4;
+fn draw_crosshair<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    x: i16,
+    y: i16,
+    color: (u8, u8, u8, u8),
+) {
+    canvas
+        .circle(x, y, HUNTER_AIM_SIZE, color)
+        .expect("Could not draw crosshair");
+    canvas
+        .hline(x - HUNTER_AIM_SIZE * 2, x + HUNTER_AIM_SIZE * 2, y, color)
+        .expect("Could not draw crosshair");
+    canvas
+        .vline(x, y - HUNTER_AIM_SIZE * 2, y + HUNTER_AIM_SIZE * 2, color)
+        .expect("Could not draw crosshair");
+}
+const SNIPER_VISUAL_HALF_SIZE: i16 = 5;
+const SNIPER_COLOR: (u8, u8, u8, u8) = (150, 0, 0, 255);
+const SNIPER_AIMING_COLOR: (u8, u8, u8, u8) = (255, 0, 0, 70);
+const SNIPER_LOCKED_COLOR: (u8, u8, u8, u8) = (255, 0, 0, 200);
+const SNIPER_LOCKED_BLINK_RATE: u64 = 6;
+const LASER_COLOR: (u8, u8, u8, u8) = (255, 0, 0, 255);
+fn draw_telegraph<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    x: i16,
+    y: i16,
+    x2: i16,
+    y2: i16,
+    color: (u8, u8, u8, u8),
+) {
+    canvas
+        .aa_line(x, y, x2, y2, color)
+        .expect("Could not draw telegraph");
+}
+const SPLIT_RING_MAX_GAP: f64 = 10.0;
+const SPLIT_COLOR: (u8, u8, u8, u8) = (255, 0, 0, 160);
+// Once this close to splitting, show which way the fragments will go
+const SPLIT_CRACKS_FROM: f64 = 0.5;
+const SPLIT_CRACK_LENGTH: f64 = 4.0;
+fn draw_split_warning<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    splitter: &Splitter,
+    dasher: &Dasher,
+    position: &Position,
+    x: i16,
+    y: i16,
+    radius: i16,
+) {
+    let dash_progress = dasher.dashes as f64 / splitter.split_after_dashes as f64;
+    let depth_progress = position.y / splitter.split_at_y;
+    let progress = dash_progress.max(depth_progress).min(1.0);
+    // The ring closes in on the dasher as it gets ready to split
+    let ring_radius = radius + (SPLIT_RING_MAX_GAP * (1.0 - progress)) as i16 + 1;
+    canvas
+        .circle(x, y, ring_radius, SPLIT_COLOR)
+        .expect("Could not draw split warning");
+    if progress < SPLIT_CRACKS_FROM {
+        return;
+    }
+    for (fx, fy) in splitter.get_fragment_directions(dasher) {
+        let inner = radius as f64;
+        let outer = inner + SPLIT_CRACK_LENGTH;
+        canvas
+            .aa_line(
+                x + (fx * inner) as i16,
+                y + (fy * inner) as i16,
+                x + (fx * outer) as i16,
+                y + (fy * outer) as i16,
+                SPLIT_COLOR,
+            )
+            .expect("Could not draw split warning");
+    }
+}
+const MINE_VISUAL_RADIUS: i16 = 5;
+const MINE_ARMING_COLOR: (u8, u8, u8, u8) = (120, 120, 120, 255);
+const MINE_ARMED_COLOR: (u8, u8, u8, u8) = (180, 0, 0, 255);
+const MINE_RANGE_COLOR: (u8, u8, u8, u8) = (180, 0, 0, 60);
+const MINE_BLINK_RATE: u64 = 20;
+const MINE_BLAST_COLOR: (u8, u8, u8, u8) = (255, 80, 0, 255);
+fn draw_mine<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    mine: &Mine,
+    world_time: f64,
+    time: u64,
+    x: i16,
+    y: i16,
+) {
+    match mine.state {
+        MineState::Arming { until } => {
+            // A slice that fills up as the mine arms
+            let progress = 1.0 - (until - world_time) / MINE_ARM_TIME;
+            let end_angle = (progress * 360.0) as i16;
+            canvas
+                .circle(x, y, MINE_VISUAL_RADIUS, MINE_ARMING_COLOR)
+                .expect("Could not draw mine");
+            if end_angle > 0 {
+                canvas
+                    .filled_pie(
+                        x,
+                        y,
+                        MINE_VISUAL_RADIUS,
+                        -90,
+                        end_angle - 90,
+                        MINE_ARMING_COLOR,
+                    )
+                    .expect("Could not draw mine");
+            }
+        }
+        MineState::Armed { .. } => {
+            canvas
+                .circle(x, y, MINE_TRIGGER_RADIUS as i16, MINE_RANGE_COLOR)
+                .expect("Could not draw mine");
+            canvas
+                .circle(x, y, MINE_BLAST_RADIUS as i16, MINE_RANGE_COLOR)
+                .expect("Could not draw mine");
+            if time % MINE_BLINK_RATE < MINE_BLINK_RATE / 2 {
+                canvas
+                    .filled_circle(x, y, MINE_VISUAL_RADIUS, MINE_ARMED_COLOR)
+                    .expect("Could not draw mine");
+            } else {
+                canvas
+                    .circle(x, y, MINE_VISUAL_RADIUS, MINE_ARMED_COLOR)
+                    .expect("Could not draw mine");
+            }
+        }
+        MineState::Exploding { .. } => {}
+    }
+}
+const ZONE_WARNING_COLOR: (u8, u8, u8, u8) = (160, 0, 200, 160);
+const ZONE_FILL_COLOR: (u8, u8, u8, u8) = (160, 0, 200, 70);
+const ZONE_OUTLINE_COLOR: (u8, u8, u8, u8) = (160, 0, 200, 255);
+const ZONE_WARNING_BLINK_RATE: u64 = 16;
+fn draw_zone<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    zone: &Zone,
+    world_time: f64,
+    time: u64,
+    x: i16,
+    y: i16,
+) {
+    let radius = zone.radius as i16;
+    if zone.is_active(world_time) {
+        canvas
+            .filled_circle(x, y, radius, ZONE_FILL_COLOR)
+            .expect("Could not draw zone");
+        canvas
+            .circle(x, y, radius, ZONE_OUTLINE_COLOR)
+            .expect("Could not draw zone");
+    } else if time % ZONE_WARNING_BLINK_RATE < ZONE_WARNING_BLINK_RATE / 2 {
+        canvas
+            .circle(x, y, radius, ZONE_WARNING_COLOR)
+            .expect("Could not draw zone");
+    }
+}
+const WALL_COLOR: (u8, u8, u8, u8) = (90, 90, 90, 255);
+const BOSS_VISUAL_RADIUS: i16 = 20;
+const BOSS_COLOR: (u8, u8, u8, u8) = (120, 0, 0, 255);
+const BOSS_BAR_COLOR: (u8, u8, u8, u8) = (120, 0, 0, 255);
+const BOSS_PHASE_MARK_COLOR: (u8, u8, u8, u8) = (0, 0, 0, 255);
+fn draw_boss<T: RenderTarget>(canvas: &mut Canvas<T>, boss: &Boss, x: i16, y: i16) {
+    canvas
+        .filled_circle(x, y, BOSS_VISUAL_RADIUS, BOSS_COLOR)
+        .expect("Could not draw boss");
+    // One pip for each phase still to come, including this one
+    let phases_left = (BOSS_PHASES.len() - boss.phase) as i16;
+    for i in 0..phases_left {
+        let pip_x = x + (i * 2 - (phases_left - 1)) * 4;
+        canvas
+            .filled_circle(pip_x, y, 2, (255, 255, 255, 255))
+            .expect("Could not draw boss");
+    }
+}
+// The boss's timer, drawn along the bottom of the screen with a mark between each phase
+fn draw_boss_bar<T: RenderTarget>(canvas: &mut Canvas<T>, boss: &Boss, world_time: f64) {
+    let y = GAME_SCREEN_HEIGHT as i16 - METER_HEIGHT;
+    let remaining = boss.get_remaining_fraction(world_time);
+    let width = (GAME_SCREEN_WIDTH * remaining) as i16;
+    if width > 0 {
+        canvas
+            .box_(0, y, width, y + METER_HEIGHT, BOSS_BAR_COLOR)
+            .expect("Could not draw boss bar");
+    }
+    let total = Boss::get_total_duration();
+    let mut phase_end = 0.0;
+    for phase in BOSS_PHASES.iter() {
+        phase_end += phase.duration;
+        let mark_x = (GAME_SCREEN_WIDTH * (1.0 - phase_end / total)) as i16;
+        canvas
+            .vline(mark_x, y, y + METER_HEIGHT, BOSS_PHASE_MARK_COLOR)
+            .expect("Could not draw boss bar");
+    }
+}
+const PICKUP_VISUAL_HALF_SIZE: i16 = 7;
+// Pickups start blinking when they have this much world time left
+const PICKUP_BLINK_TIME: f64 = 120.0;
+const PICKUP_BLINK_RATE: u64 = 10;
+fn get_pickup_appearance(kind: PickupKind) -> (&'static str, (u8, u8, u8, u8)) {
+    match kind {
+        PickupKind::ExtraLife => ("L", (0, 170, 0, 255)),
+        PickupKind::Shield => ("S", (0, 120, 255, 255)),
+        PickupKind::Bomb => ("B", (200, 0, 200, 255)),
+        PickupKind::Slow => ("T", (0, 170, 170, 255)),
+    }
+}
+fn draw_pickup<T: RenderTarget>(canvas: &mut Canvas<T>, x: i16, y: i16, kind: PickupKind) {
+    let (letter, color) = get_pickup_appearance(kind);
+    canvas
+        .rectangle(
+            x - PICKUP_VISUAL_HALF_SIZE,
+            y - PICKUP_VISUAL_HALF_SIZE,
+            x + PICKUP_VISUAL_HALF_SIZE,
+            y + PICKUP_VISUAL_HALF_SIZE,
+            color,
+        )
+        .expect("Could not draw pickup");
+    // The built in font is 8x8
+    canvas
+        .string(x - 3, y - 3, letter, color)
+        .expect("Could not draw pickup");
+}
+const BOMB_BLAST_COLOR: (u8, u8, u8, u8) = (200, 0, 200, 255);
+const BOMB_ICON_RADIUS: i16 = 3;
+const BOMB_ICON_SPACING: i16 = 10;
+fn draw_bomb_count<T: RenderTarget>(canvas: &mut Canvas<T>, bombs: u8, y: i16) {
+    for i in 0..bombs as i16 {
+        let x = GAME_SCREEN_WIDTH as i16 - BOMB_ICON_SPACING * (i + 1);
+        canvas
+            .filled_circle(x, y, BOMB_ICON_RADIUS, BOMB_BLAST_COLOR)
+            .expect("Could not draw bomb count");
+    }
+}
+const SHIELD_VISUAL_RADIUS: i16 = 9;
+const SHIELD_COLOR: (u8, u8, u8, u8) = (0, 120, 255, 255);
 const PLAYER_INVINCIBLE_FLICKER_RATE: u64 = 30;
-pub fn draw<T: RenderTarget>(game: &Game, canvas: &mut Canvas<T>, offset_x: i16, offset_y: i16) {
-    canvas.set_draw_color((255, 255, 255, 255));
-    canvas.clear();
+const BACKGROUND_COLOR: (u8, u8, u8, u8) = (255, 255, 255, 255);
+const FOCUS_BACKGROUND_COLOR: (u8, u8, u8, u8) = (225, 235, 255, 255);
+// While focusing, arrows show this many dashes ahead instead of one
+const FOCUS_PREDICTION_DASHES: u32 = 3;
+const CHARGE_RING_GAP: i16 = 3;
+const CHARGE_RING_COLOR: (u8, u8, u8, u8) = (255, 0, 0, 140);
+// An arc around the dasher that closes as its next dash approaches
+fn draw_charge_ring<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    x: i16,
+    y: i16,
+    radius: i16,
+    charge: f64,
+) {
+    let end_angle = (charge * 360.0) as i16;
+    if end_angle <= 0 {
+        return;
+    }
+    canvas
+        .arc(
+            x,
+            y,
+            radius + CHARGE_RING_GAP,
+            -90,
+            end_angle - 90,
+            CHARGE_RING_COLOR,
+        )
+        .expect("Could not draw charge ring");
+}
+// Extra information drawn over the game, beyond what the player normally sees
+pub struct Overlays {
+    // Predicted dasher paths and hunter aims
+    pub paths: bool,
+    // The real collision shapes of the player and every danger
+    pub hitboxes: bool,
+}
+impl Default for Overlays {
+    // What's shown while playing
+    fn default() -> Self {
+        Overlays {
+            paths: true,
+            hitboxes: false,
+        }
+    }
+}
+const HITBOX_COLOR: (u8, u8, u8, u8) = (255, 0, 255, 255);
+fn draw_hitboxes<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    game: &Game,
+    positions: &HashMap<GameObjectId, Position>,
+    camera: &Camera,
+) {
     if let Some(player) = game.player.as_ref() {
-        if let Some(player_pos) = game.positions.get(&player.id) {
-            if player.invincibility_until < game.time
-                || game.time % PLAYER_INVINCIBLE_FLICKER_RATE > PLAYER_INVINCIBLE_FLICKER_RATE / 2 {
+        if let Some(position) = positions.get(&player.id) {
+            let (x, y) = camera.to_screen(position);
             canvas
-                .circle(
-                    player_pos.x as i16 - offset_x,
-                    player_pos.y as i16 - offset_y,
-                    PLAYER_VISUAL_RADIUS,
-                    (0, 0, 255, 255),
-                )
-                .expect("Failed to draw player");
+                .circle(x, y, PLAYER_DANGER_RADIUS as i16, HITBOX_COLOR)
+                .expect("Could not draw hitbox");
+        }
+    }
+    for (id, danger) in game.dangers.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        let radius = danger.radius as i16;
+        match danger.shape {
+            DangerShape::Circle => {
+                canvas
+                    .circle(x, y, radius, HITBOX_COLOR)
+                    .expect("Could not draw hitbox");
+            }
+            // A capsule, drawn as its centre line with a circle at each end
+            DangerShape::Line { dx, dy } => {
+                let (x2, y2) = camera.to_screen(&Position {
+                    x: position.x + dx,
+                    y: position.y + dy,
+                });
+                canvas
+                    .line(x, y, x2, y2, HITBOX_COLOR)
+                    .expect("Could not draw hitbox");
+                for (end_x, end_y) in [(x, y), (x2, y2)] {
+                    canvas
+                        .circle(end_x, end_y, radius, HITBOX_COLOR)
+                        .expect("Could not draw hitbox");
+                }
+            }
+            DangerShape::Ring {
+                radius: ring_radius,
+            } => {
+                let inner = (ring_radius - danger.radius).max(0.0) as i16;
+                let outer = (ring_radius + danger.radius) as i16;
+                for edge in [inner, outer] {
+                    canvas
+                        .circle(x, y, edge, HITBOX_COLOR)
+                        .expect("Could not draw hitbox");
                 }
+            }
         }
     }
+}
+// Where an object should be drawn, the given fraction of the way from the previous tick's position to the current one
+fn get_draw_position(game: &Game, id: &GameObjectId, interpolation: f64) -> Option<Position> {
+    let position = game.positions.get(id)?;
+    let drawn = match game.previous_positions.get(id) {
+        // Anything that wrapped around the edge should jump, not sweep across the arena
+        Some(previous)
+            if (position.x - previous.x).abs() < GAME_SCREEN_WIDTH / 2.0
+                && (position.y - previous.y).abs() < GAME_SCREEN_HEIGHT / 2.0 =>
+        {
+            Position {
+                x: previous.x + (position.x - previous.x) * interpolation,
+                y: previous.y + (position.y - previous.y) * interpolation,
+            }
+        }
+        _ => *position,
+    };
+    Some(drawn)
+}
+fn get_draw_positions(game: &Game, interpolation: f64) -> HashMap<GameObjectId, Position> {
+    game.positions
+        .keys()
+        .filter_map(|id| Some((*id, get_draw_position(game, id, interpolation)?)))
+        .collect()
+}
+pub fn draw<T: RenderTarget>(
+    game: &Game,
+    canvas: &mut Canvas<T>,
+    viewport: &Viewport,
+    camera: &Camera,
+    overlays: &Overlays,
+    interpolation: f64,
+) {
+    viewport.set_zoom(canvas, camera.zoom);
+    let positions = get_draw_positions(game, interpolation);
+    let focusing = game.time_scale < 1.0;
+    if focusing {
+        canvas.set_draw_color(FOCUS_BACKGROUND_COLOR);
+    } else {
+        canvas.set_draw_color(BACKGROUND_COLOR);
+    }
+    // Only the viewport, leaving any letterboxing around it alone
+    canvas.fill_rect(None).expect("Could not draw background");
+    for wall in game.arena.walls.iter() {
+        let (x, y) = camera.to_screen(&Position {
+            x: wall.x,
+            y: wall.y,
+        });
+        canvas
+            .box_(
+                x,
+                y,
+                x + wall.width as i16,
+                y + wall.height as i16,
+                WALL_COLOR,
+            )
+            .expect("Could not draw wall");
+    }
+    if let Some(player) = game.player.as_ref() {
+        if let Some(player_pos) = positions.get(&player.id) {
+            let (x, y) = camera.to_screen(player_pos);
+            if player.invincibility_until < game.time
+                || game.time % PLAYER_INVINCIBLE_FLICKER_RATE > PLAYER_INVINCIBLE_FLICKER_RATE / 2
+            {
+                canvas
+                    .circle(x, y, PLAYER_VISUAL_RADIUS, (0, 0, 255, 255))
+                    .expect("Failed to draw player");
+            }
+            if player.shield {
+                canvas
+                    .circle(x, y, SHIELD_VISUAL_RADIUS, SHIELD_COLOR)
+                    .expect("Failed to draw shield");
+            }
+        }
+    }
+    for (id, zone) in game.zones.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        draw_zone(canvas, zone, game.world_time, game.time, x, y);
+    }
+    for (id, mine) in game.mines.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        draw_mine(canvas, mine, game.world_time, game.time, x, y);
+    }
     for (id, _dasher) in game.dashers.iter() {
-        let position = game.positions.get(id).unwrap();
-        let x = position.x as i16 + offset_x;
-        let y = position.y as i16 + offset_y;
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        let radius = game
+            .dangers
+            .get(id)
+            .map_or(DASHER_VISUAL_RADIUS, |danger| danger.radius as i16);
         canvas
-            .circle(x, y, DASHER_VISUAL_RADIUS, (255, 0, 0, 255))
+            .circle(x, y, radius, (255, 0, 0, 255))
             .expect("Failed to draw dasher");
-        let ax = x + _dasher.dx as i16 * DASHER_MOVE_FOR as i16;
-        let ay = y + _dasher.dy as i16 * DASHER_MOVE_FOR as i16;
-        draw_arrow(canvas, x, y, ax, ay);
+        if let Some(splitter) = game.splitters.get(id) {
+            draw_split_warning(canvas, splitter, _dasher, position, x, y, radius);
+        }
+        if let Some(charge) = _dasher.pattern.get_dash_charge(game.world_time) {
+            draw_charge_ring(canvas, x, y, radius, charge);
+        }
+        if !overlays.paths {
+            continue;
+        }
+        // Enough to show a full dash from anywhere in the dasher's cycle
+        let cycle_ticks = _dasher.pattern.get_cycle_length() as u32 + 1;
+        let prediction_ticks = if focusing {
+            cycle_ticks * FOCUS_PREDICTION_DASHES
+        } else {
+            cycle_ticks
+        };
+        let danger_radius = game
+            .dangers
+            .get(id)
+            .map_or(DASHER_RADIUS, |danger| danger.radius);
+        // Predicted from where the dasher really is, not where it is drawn
+        let path = _dasher.predict_path(
+            game.positions.get(id).unwrap(),
+            game.world_time,
+            prediction_ticks,
+            &game.arena,
+            danger_radius,
+        );
+        // Hunters show where they will aim next, so they can be predicted in turn
+        if let Some(hunter) = game.hunters.get(id) {
+            draw_path(canvas, &path, camera, HUNTER_COLOR);
+            if hunter.aims_remaining > 0 {
+                if let Some(aim) = hunter.get_aim_point(game) {
+                    let (aim_x, aim_y) = camera.to_screen(&aim);
+                    draw_crosshair(canvas, aim_x, aim_y, HUNTER_AIM_COLOR);
+                }
+            }
+        } else {
+            draw_path(canvas, &path, camera, LINE_COLOR);
+        }
+    }
+    for (id, sniper) in game.snipers.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        let (x2, y2) = camera.to_screen(&Position {
+            x: position.x + sniper.aim_dx * LASER_LENGTH,
+            y: position.y + sniper.aim_dy * LASER_LENGTH,
+        });
+        match sniper.state {
+            SniperState::Aiming { .. } => {
+                draw_telegraph(canvas, x, y, x2, y2, SNIPER_AIMING_COLOR);
+            }
+            SniperState::Locked { .. } => {
+                if game.time % SNIPER_LOCKED_BLINK_RATE < SNIPER_LOCKED_BLINK_RATE / 2 {
+                    draw_telegraph(canvas, x, y, x2, y2, SNIPER_LOCKED_COLOR);
+                }
+            }
+            SniperState::Firing { .. } => {}
+        }
+        canvas
+            .box_(
+                x - SNIPER_VISUAL_HALF_SIZE,
+                y - SNIPER_VISUAL_HALF_SIZE,
+                x + SNIPER_VISUAL_HALF_SIZE,
+                y + SNIPER_VISUAL_HALF_SIZE,
+                SNIPER_COLOR,
+            )
+            .expect("Failed to draw sniper");
+    }
+    for (id, danger) in game.dangers.iter() {
+        if let DangerShape::Line { dx, dy } = danger.shape {
+            let position = positions.get(id).unwrap();
+            let (x, y) = camera.to_screen(position);
+            let (x2, y2) = camera.to_screen(&Position {
+                x: position.x + dx,
+                y: position.y + dy,
+            });
+            canvas
+                .thick_line(x, y, x2, y2, (danger.radius * 2.0) as u8, LASER_COLOR)
+                .expect("Failed to draw laser");
+        }
+        if let DangerShape::Ring { radius } = danger.shape {
+            let position = positions.get(id).unwrap();
+            let (x, y) = camera.to_screen(position);
+            let inner = (radius - danger.radius).max(0.0) as i16;
+            let outer = (radius + danger.radius) as i16;
+            for ring_radius in inner..=outer {
+                canvas
+                    .circle(x, y, ring_radius, MINE_BLAST_COLOR)
+                    .expect("Failed to draw blast ring");
+            }
+        }
+    }
+    for (id, boss) in game.bosses.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        draw_boss(canvas, boss, x, y);
+    }
+    for (id, pickup) in game.pickups.iter() {
+        if pickup.expires_at - game.world_time < PICKUP_BLINK_TIME
+            && game.time % PICKUP_BLINK_RATE < PICKUP_BLINK_RATE / 2
+        {
+            continue;
+        }
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        draw_pickup(canvas, x, y, pickup.kind);
+    }
+    for (id, blast) in game.bomb_blasts.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        let elapsed = BOMB_BLAST_DURATION - blast.expires_at.saturating_sub(game.time);
+        let radius = blast.radius * elapsed as f64 / BOMB_BLAST_DURATION as f64;
+        canvas
+            .circle(x, y, radius as i16, BOMB_BLAST_COLOR)
+            .expect("Failed to draw bomb blast");
+    }
+    for (id, spark) in game.sparks.iter() {
+        let position = positions.get(id).unwrap();
+        let (x, y) = camera.to_screen(position);
+        let remaining = spark.expires_at.saturating_sub(game.time) as f64;
+        let size = (SPARK_MAX_SIZE * remaining / SPARK_DURATION as f64) as i16;
+        draw_spark(canvas, x, y, size);
+    }
+    if overlays.hitboxes {
+        draw_hitboxes(canvas, game, &positions, camera);
+    }
+    // The HUD stays put however the camera is zoomed
+    viewport.set_zoom(canvas, 1.0);
+    for boss in game.bosses.values() {
+        draw_boss_bar(canvas, boss, game.world_time);
+    }
+    if let Some(player) = game.player.as_ref() {
+        draw_meter(canvas, player.graze_meter, MAX_GRAZE_METER, 0, METER_COLOR);
+        let dodge_charge = DODGE_COOLDOWN - player.dodge_ready_at.saturating_sub(game.time);
+        draw_meter(
+            canvas,
+            dodge_charge as u32,
+            DODGE_COOLDOWN as u32,
+            METER_HEIGHT,
+            DODGE_COOLDOWN_COLOR,
+        );
+        draw_bomb_count(canvas, player.bombs, METER_HEIGHT * 3 + BOMB_ICON_RADIUS);
+    }
+}
+const TEXT_COLOR: (u8, u8, u8, u8) = (0, 0, 0, 255);
+const HIGHLIGHT_TEXT_COLOR: (u8, u8, u8, u8) = (200, 0, 0, 255);
+const OVERLAY_COLOR: (u8, u8, u8, u8) = (255, 255, 255, 220);
+// The height of SDL_gfx's built in font, plus some spacing
+const TEXT_LINE_HEIGHT: i16 = 12;
+const TEXT_MARGIN: i16 = 20;
+fn draw_overlay<T: RenderTarget>(canvas: &mut Canvas<T>) {
+    canvas
+        .box_(
+            0,
+            0,
+            GAME_SCREEN_WIDTH as i16,
+            GAME_SCREEN_HEIGHT as i16,
+            OVERLAY_COLOR,
+        )
+        .expect("Could not draw overlay");
+}
+pub fn draw_paused<T: RenderTarget>(canvas: &mut Canvas<T>) {
+    draw_overlay(canvas);
+    canvas
+        .string(TEXT_MARGIN, TEXT_MARGIN, "PAUSED", TEXT_COLOR)
+        .expect("Could not draw text");
+}
+// Lists every action with its keys, highlighting the one waiting for a new key
+pub fn draw_rebinding<T: RenderTarget>(
+    canvas: &mut Canvas<T>,
+    bindings: &Bindings<Keycode>,
+    index: usize,
+) {
+    draw_overlay(canvas);
+    let mut y = TEXT_MARGIN;
+    for line in [
+        "PRESS A KEY FOR THE HIGHLIGHTED ACTION",
+        "Backspace keeps it, Escape stops",
+    ] {
+        canvas
+            .string(TEXT_MARGIN, y, line, TEXT_COLOR)
+            .expect("Could not draw text");
+        y += TEXT_LINE_HEIGHT;
+    }
+    y += TEXT_LINE_HEIGHT;
+    for (i, action) in ACTIONS.iter().enumerate() {
+        let keys = bindings
+            .get_keys(*action)
+            .iter()
+            .map(|key| key.name())
+            .collect::<Vec<_>>()
+            .join(", ");
+        let color = if i == index {
+            HIGHLIGHT_TEXT_COLOR
+        } else {
+            TEXT_COLOR
+        };
+        canvas
+            .string(
+                TEXT_MARGIN,
+                y,
+                &format!("{:<12}{}", action.get_name(), keys),
+                color,
+            )
+            .expect("Could not draw text");
+        y += TEXT_LINE_HEIGHT;
     }
-    canvas.present();
 }
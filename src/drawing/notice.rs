@@ -0,0 +1,49 @@
+use sdl2::gfx::primitives::DrawRenderer;
+use sdl2::render::{Canvas, RenderTarget};
+
+use super::{TEXT_COLOR, TEXT_LINE_HEIGHT, TEXT_MARGIN};
+use crate::game::{GameEvent, GAME_SCREEN_HEIGHT};
+
+// In seconds
+const NOTICE_DURATION: f64 = 2.0;
+
+// A line of text along the bottom of the screen saying what just happened, replaced by anything newer
+pub struct Notice {
+    text: String,
+    seconds_left: f64,
+}
+
+impl Notice {
+    pub fn new() -> Self {
+        Notice {
+            text: String::new(),
+            seconds_left: 0.0,
+        }
+    }
+    pub fn handle_events(&mut self, events: &[GameEvent]) {
+        for event in events {
+            self.text = match event {
+                GameEvent::PickupSpawned(kind) => format!("A {} appeared", kind.get_name()),
+                GameEvent::PickupCollected(kind) => format!("Got the {}", kind.get_name()),
+                _ => continue,
+            };
+            self.seconds_left = NOTICE_DURATION;
+        }
+    }
+    pub fn update(&mut self, seconds: f64) {
+        self.seconds_left = (self.seconds_left - seconds).max(0.0);
+    }
+    pub fn draw<T: RenderTarget>(&self, canvas: &mut Canvas<T>) {
+        if self.seconds_left == 0.0 {
+            return;
+        }
+        canvas
+            .string(
+                TEXT_MARGIN,
+                GAME_SCREEN_HEIGHT as i16 - TEXT_MARGIN - TEXT_LINE_HEIGHT,
+                &self.text,
+                TEXT_COLOR,
+            )
+            .expect("Could not draw text");
+    }
+}
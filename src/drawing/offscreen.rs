@@ -0,0 +1,41 @@
+use sdl2::{pixels::PixelFormatEnum, surface::Surface};
+
+use super::*;
+
+// Plain RGB, three bytes a pixel, so frames can be compared or written out directly
+pub const OFFSCREEN_FORMAT: PixelFormatEnum = PixelFormatEnum::RGB24;
+
+// Draws the game into an in-memory surface with SDL's software renderer, so no window or GPU is needed
+pub fn render_offscreen(
+    game: &Game,
+    camera: &Camera,
+    overlays: &Overlays,
+    interpolation: f64,
+    width: u32,
+    height: u32,
+) -> Result<Surface<'static>, String> {
+    let surface = Surface::new(width, height, OFFSCREEN_FORMAT)?;
+    let mut canvas = surface.into_canvas()?;
+    let viewport = Viewport::fit((width, height), (width, height), false);
+    viewport.apply(&mut canvas);
+    draw(
+        game,
+        &mut canvas,
+        &viewport,
+        camera,
+        overlays,
+        interpolation,
+    );
+    Ok(canvas.into_surface())
+}
+
+// Calls back with each row of the surface's pixels, skipping any padding SDL adds to the end of rows
+pub fn for_each_row<F: FnMut(&[u8])>(surface: &Surface, mut f: F) {
+    let row_length = surface.width() as usize * surface.pixel_format_enum().byte_size_per_pixel();
+    let pitch = surface.pitch() as usize;
+    surface.with_lock(|pixels| {
+        for row in 0..surface.height() as usize {
+            f(&pixels[row * pitch..row * pitch + row_length]);
+        }
+    });
+}
@@ -0,0 +1,66 @@
+use sdl2::{
+    pixels::Color,
+    rect::Rect,
+    render::{Canvas, RenderTarget},
+};
+
+use crate::game::{Position, GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH};
+
+const LETTERBOX_COLOR: Color = Color::RGB(0, 0, 0);
+
+// Where the arena sits on the output, scaled to fit as large as it can with bars along any space left over
+pub struct Viewport {
+    // The arena's top left corner, in output pixels
+    pub x: f64,
+    pub y: f64,
+    // Output pixels per arena unit
+    pub scale: f64,
+    // Output pixels per window unit, which is more than one on high DPI displays
+    pub pixel_ratio: f64,
+}
+
+impl Viewport {
+    pub fn fit(output_size: (u32, u32), window_size: (u32, u32), integer_scaling: bool) -> Self {
+        let (output_width, output_height) = (output_size.0 as f64, output_size.1 as f64);
+        let mut scale = (output_width / GAME_SCREEN_WIDTH).min(output_height / GAME_SCREEN_HEIGHT);
+        // Whole pixels stay crisp, unless the output is too small for even one
+        if integer_scaling && scale >= 1.0 {
+            scale = scale.floor();
+        }
+        Viewport {
+            x: ((output_width - GAME_SCREEN_WIDTH * scale) / 2.0).floor(),
+            y: ((output_height - GAME_SCREEN_HEIGHT * scale) / 2.0).floor(),
+            scale,
+            pixel_ratio: output_width / window_size.0.max(1) as f64,
+        }
+    }
+    // Blacks out the whole output, leaving the canvas set up to draw the arena unzoomed
+    pub fn apply<T: RenderTarget>(&self, canvas: &mut Canvas<T>) {
+        canvas.set_viewport(None);
+        canvas.set_scale(1.0, 1.0).expect("Could not reset scale");
+        canvas.set_draw_color(LETTERBOX_COLOR);
+        canvas.clear();
+        self.set_zoom(canvas, 1.0);
+    }
+    /* Sets the canvas up so anything drawn in arena coordinates lands inside the viewport, magnified by the
+    zoom. SDL applies the scale to the viewport rectangle too, so it is given in zoomed units. */
+    pub fn set_zoom<T: RenderTarget>(&self, canvas: &mut Canvas<T>, zoom: f64) {
+        let scale = self.scale * zoom;
+        canvas
+            .set_scale(scale as f32, scale as f32)
+            .expect("Could not set scale");
+        canvas.set_viewport(Rect::new(
+            (self.x / scale).round() as i32,
+            (self.y / scale).round() as i32,
+            (GAME_SCREEN_WIDTH / zoom).round() as u32,
+            (GAME_SCREEN_HEIGHT / zoom).round() as u32,
+        ));
+    }
+    // Mouse events arrive in window coordinates, and come out in unzoomed arena-sized view coordinates
+    pub fn window_to_arena(&self, x: i32, y: i32) -> Position {
+        Position {
+            x: (x as f64 * self.pixel_ratio - self.x) / self.scale,
+            y: (y as f64 * self.pixel_ratio - self.y) / self.scale,
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use std::fs;
+
+use super::*;
+
+// An axis-aligned block that the player can't move through
+#[derive(Clone)]
+pub struct Wall {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Wall {
+    pub fn overlaps_circle(&self, position: &Position, radius: f64) -> bool {
+        let closest = Position {
+            x: position.x.clamp(self.x, self.x + self.width),
+            y: position.y.clamp(self.y, self.y + self.height),
+        };
+        position.is_closer_than(&closest, radius)
+    }
+}
+
+// What happens to a dasher that runs into a wall
+#[derive(Clone, Copy)]
+pub enum WallBehaviour {
+    PassThrough,
+    Bounce,
+    Destroy,
+}
+
+#[derive(Clone)]
+pub struct Arena {
+    pub walls: Vec<Wall>,
+    pub dasher_behaviour: WallBehaviour,
+}
+
+impl Arena {
+    pub fn empty() -> Self {
+        Arena {
+            walls: Vec::new(),
+            dasher_behaviour: WallBehaviour::PassThrough,
+        }
+    }
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read level {}: {}", path, error))?;
+        Arena::parse(&text).map_err(|error| format!("Bad level {}: {}", path, error))
+    }
+    /* One command per line, blank lines and lines starting with # are ignored:
+    wall <x> <y> <width> <height>
+    dashers pass|bounce|destroy */
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut arena = Arena::empty();
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let mut words = line.split_whitespace();
+            match words.next() {
+                None => {}
+                Some(word) if word.starts_with('#') => {}
+                Some("wall") => {
+                    let numbers = words
+                        .map(|word| word.parse::<f64>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|error| format!("line {}: {}", line_number, error))?;
+                    if numbers.len() != 4 {
+                        return Err(format!(
+                            "line {}: wall needs x, y, width and height",
+                            line_number
+                        ));
+                    }
+                    arena.walls.push(Wall {
+                        x: numbers[0],
+                        y: numbers[1],
+                        width: numbers[2],
+                        height: numbers[3],
+                    });
+                }
+                Some("dashers") => {
+                    arena.dasher_behaviour = match words.next() {
+                        Some("pass") => WallBehaviour::PassThrough,
+                        Some("bounce") => WallBehaviour::Bounce,
+                        Some("destroy") => WallBehaviour::Destroy,
+                        _ => {
+                            return Err(format!(
+                                "line {}: dashers must pass, bounce or destroy",
+                                line_number
+                            ))
+                        }
+                    };
+                }
+                Some(word) => {
+                    return Err(format!("line {}: unknown command {}", line_number, word));
+                }
+            }
+        }
+        Ok(arena)
+    }
+    pub fn is_blocked(&self, position: &Position, radius: f64) -> bool {
+        self.walls
+            .iter()
+            .any(|wall| wall.overlaps_circle(position, radius))
+    }
+}
@@ -0,0 +1,54 @@
+use super::*;
+
+pub const BOMB_BLAST_DURATION: u64 = 20;
+pub struct BombBlast {
+    pub radius: f64,
+    pub expires_at: u64,
+}
+
+impl BombBlast {
+    // Clears every danger within the radius, refunding some of their threat to the spawner
+    pub fn detonate(game: &mut Game, x: f64, y: f64, radius: f64) -> GameObjectId {
+        let id = game.create_game_object(x, y);
+        game.events.push(GameEvent::BombDetonated);
+        let centre = Position { x, y };
+        let mut cleared = Vec::new();
+        for (danger_id, danger) in game.dangers.iter() {
+            // Bosses have to be survived, not blown up
+            if game.deleted.contains_key(danger_id) || game.bosses.contains_key(danger_id) {
+                continue;
+            }
+            let position = game
+                .positions
+                .get(danger_id)
+                .expect("Danger had no position");
+            let position = danger.get_closest_point(position, &centre);
+            if position.is_closer_than(&centre, radius) {
+                cleared.push(*danger_id);
+            }
+        }
+        WaveSpawner::refund_cleared(game, &cleared);
+        for danger_id in cleared {
+            game.delete(danger_id);
+        }
+        game.bomb_blasts.insert(
+            id,
+            BombBlast {
+                radius,
+                expires_at: game.time + BOMB_BLAST_DURATION,
+            },
+        );
+        id
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_delete = Vec::new();
+        for (id, blast) in game.bomb_blasts.iter() {
+            if blast.expires_at <= game.time {
+                to_delete.push(*id);
+            }
+        }
+        for id in to_delete {
+            game.delete(id);
+        }
+    }
+}
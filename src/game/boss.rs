@@ -0,0 +1,168 @@
+use std::f64::consts::{PI, TAU};
+
+use rand::Rng;
+
+use super::*;
+
+// Bosses have no health, as the player can't fight back. Surviving every phase defeats them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BossAttack {
+    // A spread of dashers aimed down from the boss
+    Fan,
+    // Dashers dropping from random points along the top of the screen
+    Rain,
+    // Dashers fired out from the boss in every direction
+    Ring,
+}
+
+pub struct BossPhase {
+    pub attack: BossAttack,
+    pub duration: f64,
+    pub attack_every: f64,
+}
+
+pub const BOSS_PHASES: [BossPhase; 3] = [
+    BossPhase {
+        attack: BossAttack::Fan,
+        duration: 600.0,
+        attack_every: 60.0,
+    },
+    BossPhase {
+        attack: BossAttack::Rain,
+        duration: 600.0,
+        attack_every: 40.0,
+    },
+    BossPhase {
+        attack: BossAttack::Ring,
+        duration: 600.0,
+        attack_every: 90.0,
+    },
+];
+const BOSS_RADIUS: f64 = 20.0;
+const BOSS_Y: f64 = 50.0;
+const BOSS_SWAY: f64 = GAME_SCREEN_WIDTH / 3.0;
+const BOSS_SWAY_PERIOD: f64 = 480.0;
+const BOSS_DASHER_SPEED: f64 = 2.0;
+const BOSS_SPEED_PER_LEVEL: f64 = 0.25;
+const FAN_SIZE: u32 = 5;
+const FAN_SPREAD: f64 = PI / 2.0;
+const RAIN_SIZE: u32 = 4;
+const RING_SIZE: u32 = 12;
+// Later bosses fire more dashers in each attack
+const EXTRA_DASHERS_PER_LEVEL: u32 = 1;
+const BOSS_REWARD_SCORE: u32 = 5000;
+const BOSS_REWARD_LIVES: u8 = 1;
+
+pub struct Boss {
+    // How many bosses came before this one
+    pub level: u32,
+    pub phase: usize,
+    pub phase_started: f64,
+    next_attack: f64,
+    started: f64,
+}
+
+impl Boss {
+    pub fn new(game: &mut Game, level: u32) -> GameObjectId {
+        let id = game.create_game_object(GAME_SCREEN_WIDTH / 2.0, BOSS_Y);
+        game.bosses.insert(
+            id,
+            Boss {
+                level,
+                phase: 0,
+                phase_started: game.world_time,
+                next_attack: game.world_time + BOSS_PHASES[0].attack_every,
+                started: game.world_time,
+            },
+        );
+        Danger::new(game, id, BOSS_RADIUS);
+        if let Some(danger) = game.dangers.get_mut(&id) {
+            danger.consumed_on_hit = false;
+        }
+        game.events.push(GameEvent::BossStarted);
+        id
+    }
+    pub fn get_total_duration() -> f64 {
+        BOSS_PHASES.iter().map(|phase| phase.duration).sum()
+    }
+    // How much of the fight is left, from 1 down to 0
+    pub fn get_remaining_fraction(&self, world_time: f64) -> f64 {
+        let elapsed = world_time - self.started;
+        (1.0 - elapsed / Boss::get_total_duration()).clamp(0.0, 1.0)
+    }
+    fn attack(game: &mut Game, attack: BossAttack, level: u32, x: f64, y: f64) {
+        let speed = BOSS_DASHER_SPEED + BOSS_SPEED_PER_LEVEL * level as f64;
+        let extra = EXTRA_DASHERS_PER_LEVEL * level;
+        match attack {
+            BossAttack::Fan => {
+                let count = FAN_SIZE + extra;
+                for i in 0..count {
+                    let angle = PI / 2.0 + FAN_SPREAD * (i as f64 / (count - 1) as f64 - 0.5);
+                    Dasher::new(game, x, y, speed * angle.cos(), speed * angle.sin());
+                }
+            }
+            BossAttack::Rain => {
+                for _ in 0..RAIN_SIZE + extra {
+                    let rain_x = game.rng.gen_range(0.0..GAME_SCREEN_WIDTH);
+                    Dasher::new(game, rain_x, 0.0, 0.0, speed);
+                }
+            }
+            BossAttack::Ring => {
+                let count = RING_SIZE + extra;
+                for i in 0..count {
+                    let angle = TAU * i as f64 / count as f64;
+                    let id = Dasher::new(game, x, y, speed * angle.cos(), speed * angle.sin());
+                    if let Some(dasher) = game.dashers.get_mut(&id) {
+                        dasher.wraps = false;
+                    }
+                }
+            }
+        }
+    }
+    fn reward(game: &mut Game) {
+        game.bonus_score += BOSS_REWARD_SCORE;
+        if let Some(player) = game.player.as_mut() {
+            player.lives = (player.lives + BOSS_REWARD_LIVES).min(MAX_LIVES);
+        }
+    }
+    pub fn step(game: &mut Game) {
+        let mut attacks = Vec::new();
+        let mut defeated = Vec::new();
+        let mut phase_changes = Vec::new();
+        for (id, boss) in game.bosses.iter_mut() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            let position = game.positions.get_mut(id).expect("Boss had no position");
+            let sway = (TAU * (game.world_time - boss.started) / BOSS_SWAY_PERIOD).sin();
+            position.x = GAME_SCREEN_WIDTH / 2.0 + BOSS_SWAY * sway;
+            let phase = &BOSS_PHASES[boss.phase];
+            if boss.phase_started + phase.duration <= game.world_time {
+                boss.phase += 1;
+                if boss.phase >= BOSS_PHASES.len() {
+                    defeated.push(*id);
+                    continue;
+                }
+                boss.phase_started = game.world_time;
+                boss.next_attack = game.world_time + BOSS_PHASES[boss.phase].attack_every;
+                phase_changes.push(boss.phase);
+                continue;
+            }
+            if boss.next_attack <= game.world_time {
+                boss.next_attack = game.world_time + phase.attack_every;
+                attacks.push((phase.attack, boss.level, position.x, position.y));
+            }
+        }
+        for phase in phase_changes {
+            game.events.push(GameEvent::BossPhaseChanged(phase));
+        }
+        for (attack, level, x, y) in attacks {
+            Boss::attack(game, attack, level, x, y);
+        }
+        for id in defeated {
+            game.delete(id);
+            Boss::reward(game);
+            game.events.push(GameEvent::BossDefeated);
+        }
+    }
+}
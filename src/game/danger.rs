@@ -1,40 +1,146 @@
 use super::*;
 
-const PLAYER_DANGER_RADIUS: f64 = 5.0;
+pub const PLAYER_DANGER_RADIUS: f64 = 5.0;
+pub enum DangerShape {
+    Circle,
+    // A segment from the danger's position to its position plus (dx, dy)
+    Line { dx: f64, dy: f64 },
+    // A circle's outline, centred on the danger's position
+    Ring { radius: f64 },
+}
 pub struct Danger {
-    radius: f64,
+    pub radius: f64,
+    pub shape: DangerShape,
+    // Most dangers are used up by hitting the player, but some stay around to hit again
+    pub consumed_on_hit: bool,
+    grazed: bool,
 }
 
 const HIT_INVINCIBILITY_TIME: u64 = 180;
+// How much further than a hit a danger can pass and still count as a graze
+const GRAZE_DISTANCE: f64 = 15.0;
+const GRAZE_SCORE: u32 = 50;
+const GRAZE_METER_GAIN: u32 = 10;
 impl Danger {
     pub fn new(game: &mut Game, id: GameObjectId, radius: f64) {
-        let danger = Danger { radius };
+        let danger = Danger {
+            radius,
+            shape: DangerShape::Circle,
+            consumed_on_hit: true,
+            grazed: false,
+        };
+        game.dangers.insert(id, danger);
+    }
+    pub fn new_line(game: &mut Game, id: GameObjectId, radius: f64, dx: f64, dy: f64) {
+        let danger = Danger {
+            radius,
+            shape: DangerShape::Line { dx, dy },
+            consumed_on_hit: true,
+            grazed: false,
+        };
+        game.dangers.insert(id, danger);
+    }
+    pub fn new_ring(game: &mut Game, id: GameObjectId, thickness: f64, ring_radius: f64) {
+        let danger = Danger {
+            radius: thickness,
+            shape: DangerShape::Ring {
+                radius: ring_radius,
+            },
+            consumed_on_hit: true,
+            grazed: false,
+        };
         game.dangers.insert(id, danger);
     }
+    // The point of the danger's centre line nearest to the given point
+    pub fn get_closest_point(&self, danger_pos: &Position, point: &Position) -> Position {
+        match self.shape {
+            DangerShape::Circle => Position {
+                x: danger_pos.x,
+                y: danger_pos.y,
+            },
+            DangerShape::Line { dx, dy } => {
+                let length_sq = dx * dx + dy * dy;
+                let along = if length_sq == 0.0 {
+                    0.0
+                } else {
+                    (((point.x - danger_pos.x) * dx + (point.y - danger_pos.y) * dy) / length_sq)
+                        .clamp(0.0, 1.0)
+                };
+                Position {
+                    x: danger_pos.x + dx * along,
+                    y: danger_pos.y + dy * along,
+                }
+            }
+            DangerShape::Ring { radius } => {
+                let dx = point.x - danger_pos.x;
+                let dy = point.y - danger_pos.y;
+                let distance = dx.hypot(dy);
+                if distance == 0.0 {
+                    return Position {
+                        x: danger_pos.x + radius,
+                        y: danger_pos.y,
+                    };
+                }
+                Position {
+                    x: danger_pos.x + dx * radius / distance,
+                    y: danger_pos.y + dy * radius / distance,
+                }
+            }
+        }
+    }
     pub fn step(game: &mut Game) {
         let mut hit_player = Vec::new();
+        let mut grazes = Vec::new();
         let mut damaged = false;
         if let Some(player) = game.player.as_ref() {
             if player.invincibility_until > game.time {
                 return;
             }
             if let Some(position) = game.positions.get(&player.id) {
-                for (id, danger) in game.dangers.iter() {
+                for (id, danger) in game.dangers.iter_mut() {
+                    if game.deleted.contains_key(id) {
+                        continue;
+                    }
                     let danger_pos = game.positions.get(&id).expect("Danger had no position");
+                    let danger_pos = &danger.get_closest_point(danger_pos, position);
                     if position.is_closer_than(danger_pos, PLAYER_DANGER_RADIUS + danger.radius) {
                         damaged = true;
-                        hit_player.push(*id);
+                        if danger.consumed_on_hit {
+                            hit_player.push(*id);
+                        }
+                    } else if !danger.grazed
+                        && position.is_closer_than(
+                            danger_pos,
+                            PLAYER_DANGER_RADIUS + danger.radius + GRAZE_DISTANCE,
+                        )
+                    {
+                        danger.grazed = true;
+                        grazes.push(Position {
+                            x: (position.x + danger_pos.x) / 2.0,
+                            y: (position.y + danger_pos.y) / 2.0,
+                        });
                     }
                 }
             }
         }
+        for graze in grazes {
+            game.bonus_score += GRAZE_SCORE;
+            if let Some(player) = game.player.as_mut() {
+                player.graze_meter = (player.graze_meter + GRAZE_METER_GAIN).min(MAX_GRAZE_METER);
+            }
+            Spark::new(game, graze.x, graze.y);
+        }
         if damaged {
+            game.events.push(GameEvent::PlayerHit);
             for id in hit_player {
-                game.deleted.insert(id, ());
+                game.delete(id);
             }
             if let Some(player) = game.player.as_mut() {
                 player.invincibility_until = game.time + HIT_INVINCIBILITY_TIME;
-                if player.lives == 0 {
+                if player.shield {
+                    player.shield = false;
+                }
+                else if player.lives == 0 {
                     game.player = None;
                 }
                 else {
@@ -1,65 +1,176 @@
 use super::*;
 
 pub struct Dasher {
-    move_next: u64,
-    move_until: u64,
+    pub pattern: Box<dyn MovementPattern>,
     pub dx: f64,
     pub dy: f64,
-    pub delete_next_tick: bool,
+    // Number of movement cycles finished so far
+    pub dashes: u32,
+    // Whether leaving the left or right edge brings the dasher back on the other side
+    pub wraps: bool,
 }
 
-pub const DASHER_MOVE_EVERY: u64 = 20;
-pub const DASHER_MOVE_FOR: u64 = 10;
+pub const DASHER_MOVE_EVERY: f64 = 20.0;
+pub const DASHER_MOVE_FOR: f64 = 10.0;
 pub const GAME_SCREEN_WIDTH: f64 = 400.0;
 pub const GAME_SCREEN_HEIGHT: f64 = 400.0;
-const DASHER_RADIUS: f64 = 5.0;
+pub const DASHER_RADIUS: f64 = 5.0;
 
 impl Dasher {
     pub fn new(game: &mut Game, x: f64, y: f64, dx: f64, dy: f64) -> GameObjectId {
+        Dasher::new_with_radius(game, x, y, dx, dy, DASHER_RADIUS)
+    }
+    pub fn new_with_radius(
+        game: &mut Game,
+        x: f64,
+        y: f64,
+        dx: f64,
+        dy: f64,
+        radius: f64,
+    ) -> GameObjectId {
+        let pattern = Box::new(DashPattern::new(game.world_time));
+        Dasher::new_with_pattern(game, x, y, dx, dy, radius, pattern)
+    }
+    pub fn new_with_pattern(
+        game: &mut Game,
+        x: f64,
+        y: f64,
+        dx: f64,
+        dy: f64,
+        radius: f64,
+        pattern: Box<dyn MovementPattern>,
+    ) -> GameObjectId {
         let id = game.create_game_object(x, y);
         game.dashers.insert(
             id,
             Dasher {
+                pattern,
                 dx,
                 dy,
-                delete_next_tick: false,
-                move_next: game.time + DASHER_MOVE_EVERY,
-                move_until: game.time + DASHER_MOVE_EVERY + DASHER_MOVE_FOR,
+                dashes: 0,
+                wraps: true,
             },
         );
-        Danger::new(game, id, DASHER_RADIUS);
+        Danger::new(game, id, radius);
         id
     }
+    // Outside the arena and still heading away from it
+    pub fn is_leaving(&self, position: &Position) -> bool {
+        (position.y > GAME_SCREEN_HEIGHT && self.dy >= 0.0)
+            || (position.y < 0.0 && self.dy < 0.0)
+            || (position.x > GAME_SCREEN_WIDTH && self.dx >= 0.0)
+            || (position.x < 0.0 && self.dx < 0.0)
+    }
+    // Moves the dasher on by one tick, returning whether it has now left the arena or been destroyed
+    fn advance(
+        &mut self,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+        arena: &Arena,
+        radius: f64,
+    ) -> bool {
+        let previous = *position;
+        if self
+            .pattern
+            .step(&mut self.dx, &mut self.dy, position, world_time, time_scale)
+        {
+            self.dashes += 1;
+        }
+        if self.wraps {
+            if position.x > GAME_SCREEN_WIDTH {
+                position.x -= GAME_SCREEN_WIDTH;
+            }
+            if position.x < 0.0 {
+                position.x += GAME_SCREEN_WIDTH;
+            }
+        }
+        if arena.is_blocked(position, radius) && !arena.is_blocked(&previous, radius) {
+            match arena.dasher_behaviour {
+                WallBehaviour::PassThrough => {}
+                WallBehaviour::Destroy => return true,
+                WallBehaviour::Bounce => self.bounce(position, &previous, arena, radius),
+            }
+        }
+        self.is_leaving(position)
+    }
+    // Turns back along whichever axes took the dasher into the wall, and undoes the move
+    fn bounce(&mut self, position: &mut Position, previous: &Position, arena: &Arena, radius: f64) {
+        let only_x = Position {
+            x: position.x,
+            y: previous.y,
+        };
+        let only_y = Position {
+            x: previous.x,
+            y: position.y,
+        };
+        let blocked_x = arena.is_blocked(&only_x, radius);
+        let blocked_y = arena.is_blocked(&only_y, radius);
+        // Clipping a corner head on blocks neither axis alone
+        if blocked_x || !blocked_y {
+            self.dx = -self.dx;
+        }
+        if blocked_y || !blocked_x {
+            self.dy = -self.dy;
+        }
+        *position = *previous;
+    }
+    // Where the dasher will be over the next ticks of world time, starting with where it is now
+    pub fn predict_path(
+        &self,
+        position: &Position,
+        world_time: f64,
+        ticks: u32,
+        arena: &Arena,
+        radius: f64,
+    ) -> Vec<Position> {
+        let mut future = Dasher {
+            pattern: self.pattern.clone_box(),
+            dx: self.dx,
+            dy: self.dy,
+            dashes: self.dashes,
+            wraps: self.wraps,
+        };
+        let mut future_position = *position;
+        let mut path = vec![future_position];
+        for tick in 0..ticks {
+            let leaving = future.advance(
+                &mut future_position,
+                world_time + tick as f64,
+                1.0,
+                arena,
+                radius,
+            );
+            path.push(future_position);
+            if leaving {
+                break;
+            }
+        }
+        path
+    }
     pub fn step(game: &mut Game) {
         let mut to_delete = Vec::new();
         for (id, dasher) in game.dashers.iter_mut() {
-            if game.deleted.contains_key(id) || dasher.delete_next_tick {
-                to_delete.push(*id);
+            if game.deleted.contains_key(id) {
                 continue;
             }
-            if dasher.move_next <= game.time {
-                let position = game.positions.get_mut(id).expect("Dasher with no position");
-                position.x += dasher.dx;
-                position.y += dasher.dy;
-                if dasher.move_until <= game.time {
-                    dasher.move_next = game.time + DASHER_MOVE_EVERY;
-                    dasher.move_until = game.time + DASHER_MOVE_EVERY + DASHER_MOVE_FOR;
-                }
-                if position.y > GAME_SCREEN_HEIGHT {
-                    dasher.delete_next_tick = true;
-                }
-                if position.x > GAME_SCREEN_WIDTH {
-                    position.x -= GAME_SCREEN_WIDTH;
-                }
-                if position.x < 0.0 {
-                    position.x += GAME_SCREEN_WIDTH;
-                }
+            let position = game.positions.get_mut(id).expect("Dasher with no position");
+            let radius = game
+                .dangers
+                .get(id)
+                .map_or(DASHER_RADIUS, |danger| danger.radius);
+            if dasher.advance(
+                position,
+                game.world_time,
+                game.time_scale,
+                &game.arena,
+                radius,
+            ) {
+                to_delete.push(*id);
             }
         }
         for id in to_delete {
-            game.positions.remove(&id);
-            game.dashers.remove(&id);
-            game.dangers.remove(&id);
+            game.delete(id);
         }
     }
 }
@@ -0,0 +1,14 @@
+use super::PickupKind;
+
+// Things that happened during a tick, for the frontend to react to. Cleared at the start of each step.
+#[derive(Clone, Copy, Debug)]
+pub enum GameEvent {
+    PlayerHit,
+    BombDetonated,
+    BossStarted,
+    // Into the phase with this index in BOSS_PHASES
+    BossPhaseChanged(usize),
+    BossDefeated,
+    PickupSpawned(PickupKind),
+    PickupCollected(PickupKind),
+}
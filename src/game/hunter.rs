@@ -0,0 +1,95 @@
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use super::*;
+
+/* A dasher that re-aims at the start of every dash, towards where the player will be
+if they keep moving the way they are now. */
+pub struct Hunter {
+    // How far ahead of the player to aim, as a fraction of one dash's worth of player movement
+    pub lead_factor: f64,
+    // Largest change of direction per dash, in radians
+    pub max_turn: f64,
+    pub aims_remaining: u32,
+    aimed_for: f64,
+}
+
+impl Hunter {
+    pub fn new(
+        game: &mut Game,
+        x: f64,
+        y: f64,
+        speed: f64,
+        lead_factor: f64,
+        max_turn: f64,
+        aims: u32,
+    ) -> GameObjectId {
+        let id = Dasher::new(game, x, y, 0.0, speed);
+        game.hunters.insert(
+            id,
+            Hunter {
+                lead_factor,
+                max_turn,
+                aims_remaining: aims,
+                aimed_for: f64::NEG_INFINITY,
+            },
+        );
+        id
+    }
+    pub fn get_aim_point(&self, game: &Game) -> Option<Position> {
+        let player = game.player.as_ref()?;
+        let player_position = game.positions.get(&player.id)?;
+        let lead = DASHER_MOVE_FOR * self.lead_factor;
+        Some(Position {
+            x: player_position.x + player.dx * lead,
+            y: player_position.y + player.dy * lead,
+        })
+    }
+    fn get_new_angle(&self, game: &Game, position: &Position, current_angle: f64) -> f64 {
+        // Once out of aims, or with nobody to chase, head off the bottom of the screen
+        let target_angle = match self.get_aim_point(game) {
+            Some(target) if self.aims_remaining > 0 => {
+                (target.y - position.y).atan2(target.x - position.x)
+            }
+            _ => FRAC_PI_2,
+        };
+        let mut turn = (target_angle - current_angle).rem_euclid(TAU);
+        if turn > PI {
+            turn -= TAU;
+        }
+        current_angle + turn.clamp(-self.max_turn, self.max_turn)
+    }
+    pub fn step(game: &mut Game) {
+        let mut new_angles = Vec::new();
+        for (id, hunter) in game.hunters.iter() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            let dasher = game.dashers.get(id).expect("Hunter was not a dasher");
+            let dash_start = match dasher.pattern.get_next_dash_start() {
+                Some(dash_start) => dash_start,
+                None => continue,
+            };
+            if dash_start > game.world_time || hunter.aimed_for == dash_start {
+                continue;
+            }
+            let position = game.positions.get(id).expect("Hunter had no position");
+            let current_angle = dasher.dy.atan2(dasher.dx);
+            new_angles.push((
+                *id,
+                dash_start,
+                hunter.get_new_angle(game, position, current_angle),
+            ));
+        }
+        for (id, dash_start, angle) in new_angles {
+            if let Some(hunter) = game.hunters.get_mut(&id) {
+                hunter.aimed_for = dash_start;
+                hunter.aims_remaining = hunter.aims_remaining.saturating_sub(1);
+            }
+            if let Some(dasher) = game.dashers.get_mut(&id) {
+                let speed = dasher.dx.hypot(dasher.dy);
+                dasher.dx = speed * angle.cos();
+                dasher.dy = speed * angle.sin();
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use super::*;
+
+// All mine timings are in world time
+pub const MINE_ARM_TIME: f64 = 120.0;
+// An armed mine goes off by itself after this long, even if the player stays away
+const MINE_FUSE_TIME: f64 = 240.0;
+pub const MINE_TRIGGER_RADIUS: f64 = 40.0;
+pub const MINE_BLAST_RADIUS: f64 = 80.0;
+const MINE_BLAST_TIME: f64 = 30.0;
+const MINE_BLAST_THICKNESS: f64 = 4.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MineState {
+    Arming { until: f64 },
+    Armed { until: f64 },
+    // The blast is a ring danger on the mine itself, growing until it reaches full size
+    Exploding { started: f64 },
+}
+
+pub struct Mine {
+    pub state: MineState,
+}
+
+impl Mine {
+    pub fn new(game: &mut Game, x: f64, y: f64) -> GameObjectId {
+        let id = game.create_game_object(x, y);
+        game.mines.insert(
+            id,
+            Mine {
+                state: MineState::Arming {
+                    until: game.world_time + MINE_ARM_TIME,
+                },
+            },
+        );
+        id
+    }
+    // How far the blast has spread, from 0 to 1
+    pub fn get_blast_progress(started: f64, world_time: f64) -> f64 {
+        ((world_time - started) / MINE_BLAST_TIME).clamp(0.0, 1.0)
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_detonate = Vec::new();
+        let mut to_delete = Vec::new();
+        let player_position = game
+            .player
+            .as_ref()
+            .and_then(|player| game.positions.get(&player.id));
+        for (id, mine) in game.mines.iter_mut() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            let position = game.positions.get(id).expect("Mine had no position");
+            match mine.state {
+                MineState::Arming { until } => {
+                    if until <= game.world_time {
+                        mine.state = MineState::Armed {
+                            until: game.world_time + MINE_FUSE_TIME,
+                        };
+                    }
+                }
+                MineState::Armed { until } => {
+                    let triggered = player_position.is_some_and(|player_position| {
+                        player_position.is_closer_than(position, MINE_TRIGGER_RADIUS)
+                    });
+                    if triggered || until <= game.world_time {
+                        mine.state = MineState::Exploding {
+                            started: game.world_time,
+                        };
+                        to_detonate.push(*id);
+                    }
+                }
+                MineState::Exploding { started } => {
+                    let progress = Mine::get_blast_progress(started, game.world_time);
+                    if progress >= 1.0 {
+                        to_delete.push(*id);
+                    } else if let Some(danger) = game.dangers.get_mut(id) {
+                        danger.shape = DangerShape::Ring {
+                            radius: MINE_BLAST_RADIUS * progress,
+                        };
+                    }
+                }
+            }
+        }
+        for id in to_detonate {
+            Danger::new_ring(game, id, MINE_BLAST_THICKNESS, 0.0);
+        }
+        for id in to_delete {
+            game.delete(id);
+        }
+    }
+}
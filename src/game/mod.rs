@@ -5,23 +5,64 @@ use std::{
 };
 
 use byteorder::{BigEndian, ReadBytesExt};
+use rand::{rngs::StdRng, SeedableRng};
 
-use self::waves::WaveSpawner;
+use self::waves::{Threat, WaveSpawner};
 
 mod waves;
 
 mod dasher;
 pub use dasher::*;
 
+mod movement;
+pub use movement::*;
+
 mod player;
 pub use player::*;
 
 mod danger;
 pub use danger::*;
 
+mod spark;
+pub use spark::*;
+
+mod pickup;
+pub use pickup::*;
+
+mod bomb;
+pub use bomb::*;
+
+mod hunter;
+pub use hunter::*;
+
+mod sniper;
+pub use sniper::*;
+
+mod splitter;
+pub use splitter::*;
+
+mod mine;
+pub use mine::*;
+
+mod zone;
+pub use zone::*;
+
+mod boss;
+pub use boss::*;
+
+mod arena;
+pub use arena::*;
+
+mod tick_input;
+pub use tick_input::*;
+
+mod event;
+pub use event::*;
+
 #[derive(Hash, Eq, Ord, PartialEq, PartialOrd, Debug, Copy, Clone)]
 pub struct GameObjectId(u64);
 
+#[derive(Clone, Copy)]
 pub struct Position {
     pub x: f64,
     pub y: f64,
@@ -58,7 +99,7 @@ impl Hasher for GameObjectIdHasher {
         self.already_written = true;
     }
 }
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct GameObjectIdBuildHasher {}
 impl BuildHasher for GameObjectIdBuildHasher {
     type Hasher = GameObjectIdHasher;
@@ -74,12 +115,36 @@ type IdHashMap<V> = HashMap<GameObjectId, V, GameObjectIdBuildHasher>;
 pub struct Game {
     id_counter: u64,
     pub time: u64,
+    /* Time as seen by the world (dashers, waves), advanced by time_scale each tick.
+    Time scales are kept to powers of two so this stays exact. */
+    pub world_time: f64,
+    pub time_scale: f64,
     pub deleted: IdHashMap<()>,
     pub player: Option<Player>,
     pub dangers: IdHashMap<Danger>,
     pub wave_spawner: WaveSpawner,
     pub positions: IdHashMap<Position>,
+    // Where everything was at the start of the last tick, for drawing between ticks
+    pub previous_positions: IdHashMap<Position>,
     pub dashers: IdHashMap<Dasher>,
+    pub hunters: IdHashMap<Hunter>,
+    pub snipers: IdHashMap<Sniper>,
+    pub splitters: IdHashMap<Splitter>,
+    pub mines: IdHashMap<Mine>,
+    pub zones: IdHashMap<Zone>,
+    pub bosses: IdHashMap<Boss>,
+    pub sparks: IdHashMap<Spark>,
+    pub pickups: IdHashMap<Pickup>,
+    pub bomb_blasts: IdHashMap<BombBlast>,
+    // What each danger cost the wave spawner when it appeared, refunded in part if a bomb clears it
+    pub spawn_threats: IdHashMap<Threat>,
+    pub arena: Arena,
+    pub movement_model: MovementModel,
+    pub bonus_score: u32,
+    pub events: Vec<GameEvent>,
+    // All randomness in the simulation must come from here, so a seed reproduces a game
+    pub rng: StdRng,
+    pub seed: u64,
 }
 
 impl Game {
@@ -89,23 +154,89 @@ impl Game {
         self.positions.insert(id, Position { x, y });
         id
     }
-    pub fn step(&mut self) {
+    // Objects are only removed at the end of the tick, so systems should skip anything in deleted
+    pub fn delete(&mut self, id: GameObjectId) {
+        self.deleted.insert(id, ());
+    }
+    fn remove_deleted(&mut self) {
+        for id in self.deleted.keys() {
+            self.positions.remove(id);
+            self.dangers.remove(id);
+            self.dashers.remove(id);
+            self.hunters.remove(id);
+            self.snipers.remove(id);
+            self.splitters.remove(id);
+            self.mines.remove(id);
+            self.zones.remove(id);
+            self.bosses.remove(id);
+            self.sparks.remove(id);
+            self.pickups.remove(id);
+            self.bomb_blasts.remove(id);
+            self.spawn_threats.remove(id);
+        }
+        self.deleted.clear();
+    }
+    pub fn step(&mut self, input: &TickInput) {
+        self.previous_positions = self.positions.clone();
+        self.events.clear();
+        Player::apply_input(self, input);
         Player::step(self);
         Danger::step(self);
-        WaveSpawner::step(self);
+        // The spawner works in whole ticks, so run it once for every whole tick of world time
+        let next_world_time = self.world_time + self.time_scale;
+        let whole_world_ticks = next_world_time.floor() - self.world_time.floor();
+        for _ in 0..whole_world_ticks as u64 {
+            WaveSpawner::step(self);
+        }
+        Boss::step(self);
+        Hunter::step(self);
+        Sniper::step(self);
         Dasher::step(self);
+        Splitter::step(self);
+        Mine::step(self);
+        Zone::step(self);
+        Pickup::step(self);
+        Spark::step(self);
+        BombBlast::step(self);
+        self.remove_deleted();
+        self.world_time = next_world_time;
         self.time += 1;
     }
+    pub fn score(&self) -> u32 {
+        self.wave_spawner.current_difficulty + self.bonus_score
+    }
     pub fn new() -> Self {
+        Game::with_seed(rand::random())
+    }
+    pub fn with_seed(seed: u64) -> Self {
         let game = Game {
             id_counter: 0,
             time: 0,
+            world_time: 0.0,
+            time_scale: 1.0,
             player: None,
             deleted: IdHashMap::with_hasher(Default::default()),
             dangers: IdHashMap::with_hasher(Default::default()),
             wave_spawner: WaveSpawner::new(),
             positions: IdHashMap::with_hasher(Default::default()),
+            previous_positions: IdHashMap::with_hasher(Default::default()),
             dashers: IdHashMap::with_hasher(Default::default()),
+            hunters: IdHashMap::with_hasher(Default::default()),
+            snipers: IdHashMap::with_hasher(Default::default()),
+            splitters: IdHashMap::with_hasher(Default::default()),
+            mines: IdHashMap::with_hasher(Default::default()),
+            zones: IdHashMap::with_hasher(Default::default()),
+            bosses: IdHashMap::with_hasher(Default::default()),
+            sparks: IdHashMap::with_hasher(Default::default()),
+            pickups: IdHashMap::with_hasher(Default::default()),
+            bomb_blasts: IdHashMap::with_hasher(Default::default()),
+            spawn_threats: IdHashMap::with_hasher(Default::default()),
+            arena: Arena::empty(),
+            movement_model: MovementModel::default(),
+            bonus_score: 0,
+            events: Vec::new(),
+            rng: StdRng::seed_from_u64(seed),
+            seed,
         };
         game
     }
@@ -0,0 +1,362 @@
+use std::f64::consts::TAU;
+
+use super::*;
+
+/* How a dasher moves from tick to tick. The dasher's dx and dy are its heading, which
+patterns interpret in their own way and may change (for example when bouncing).
+Patterns must only depend on their arguments, so cloning one gives an exact prediction. */
+pub trait MovementPattern {
+    // Moves on by one tick, time_scale long in world time. Returns true when a cycle finishes.
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool;
+    fn clone_box(&self) -> Box<dyn MovementPattern>;
+    // For patterns that alternate moving and waiting, when the next move begins
+    fn get_next_dash_start(&self) -> Option<f64> {
+        None
+    }
+    // While waiting to move, how far through the wait the pattern is, from 0 to 1
+    fn get_dash_charge(&self, _world_time: f64) -> Option<f64> {
+        None
+    }
+    // How long one cycle takes, in world time
+    fn get_cycle_length(&self) -> f64 {
+        CYCLE_LENGTH
+    }
+}
+
+// Patterns that move all the time still count cycles of the same length as a dash
+const CYCLE_LENGTH: f64 = DASHER_MOVE_EVERY + DASHER_MOVE_FOR;
+#[derive(Clone)]
+struct CycleCounter {
+    next: f64,
+}
+impl CycleCounter {
+    fn new(world_time: f64) -> Self {
+        CycleCounter {
+            next: world_time + CYCLE_LENGTH,
+        }
+    }
+    fn step(&mut self, world_time: f64) -> bool {
+        if self.next <= world_time {
+            self.next = world_time + CYCLE_LENGTH;
+            return true;
+        }
+        false
+    }
+}
+
+// Unit vector at right angles to the heading
+fn get_perpendicular(dx: f64, dy: f64) -> (f64, f64) {
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return (1.0, 0.0);
+    }
+    (-dy / length, dx / length)
+}
+
+// Waits, then moves along the heading for a while, over and over
+#[derive(Clone)]
+pub struct DashPattern {
+    // Time spent waiting between dashes
+    pub move_every: f64,
+    // Time spent moving in each dash
+    pub move_for: f64,
+    move_next: f64,
+    move_until: f64,
+}
+impl DashPattern {
+    pub fn new(world_time: f64) -> Self {
+        DashPattern::with_timing(world_time, DASHER_MOVE_EVERY, DASHER_MOVE_FOR, 0.0)
+    }
+    // The phase delays the first dash, so dashers spawned together needn't move together
+    pub fn with_timing(world_time: f64, move_every: f64, move_for: f64, phase: f64) -> Self {
+        DashPattern {
+            move_every,
+            move_for,
+            move_next: world_time + move_every + phase,
+            move_until: world_time + move_every + phase + move_for,
+        }
+    }
+}
+impl MovementPattern for DashPattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        if self.move_next > world_time {
+            return false;
+        }
+        position.x += *dx * time_scale;
+        position.y += *dy * time_scale;
+        if self.move_until <= world_time {
+            self.move_next = world_time + self.move_every;
+            self.move_until = world_time + self.move_every + self.move_for;
+            return true;
+        }
+        false
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+    fn get_next_dash_start(&self) -> Option<f64> {
+        Some(self.move_next)
+    }
+    fn get_dash_charge(&self, world_time: f64) -> Option<f64> {
+        if self.move_next <= world_time {
+            return None;
+        }
+        Some((1.0 - (self.move_next - world_time) / self.move_every).max(0.0))
+    }
+    fn get_cycle_length(&self) -> f64 {
+        self.move_every + self.move_for
+    }
+}
+
+// Moves along the heading every tick
+#[derive(Clone)]
+pub struct GlidePattern {
+    cycles: CycleCounter,
+}
+impl GlidePattern {
+    pub fn new(world_time: f64) -> Self {
+        GlidePattern {
+            cycles: CycleCounter::new(world_time),
+        }
+    }
+}
+impl MovementPattern for GlidePattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        position.x += *dx * time_scale;
+        position.y += *dy * time_scale;
+        self.cycles.step(world_time)
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+}
+
+// Glides while swaying from side to side along a sine wave
+#[derive(Clone)]
+pub struct WeavePattern {
+    pub amplitude: f64,
+    pub period: f64,
+    started: f64,
+    cycles: CycleCounter,
+}
+impl WeavePattern {
+    pub fn new(world_time: f64, amplitude: f64, period: f64) -> Self {
+        WeavePattern {
+            amplitude,
+            period,
+            started: world_time,
+            cycles: CycleCounter::new(world_time),
+        }
+    }
+}
+impl MovementPattern for WeavePattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        let (px, py) = get_perpendicular(*dx, *dy);
+        let angular_speed = TAU / self.period;
+        // The derivative of amplitude * sin(angular_speed * t)
+        let sideways =
+            self.amplitude * angular_speed * (angular_speed * (world_time - self.started)).cos();
+        position.x += (*dx + px * sideways) * time_scale;
+        position.y += (*dy + py * sideways) * time_scale;
+        self.cycles.step(world_time)
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+}
+
+// Glides while switching between drifting left and right of the heading
+#[derive(Clone)]
+pub struct ZigZagPattern {
+    pub sideways_speed: f64,
+    // Time spent on each side before switching
+    pub period: f64,
+    started: f64,
+    cycles: CycleCounter,
+}
+impl ZigZagPattern {
+    pub fn new(world_time: f64, sideways_speed: f64, period: f64) -> Self {
+        ZigZagPattern {
+            sideways_speed,
+            period,
+            started: world_time,
+            cycles: CycleCounter::new(world_time),
+        }
+    }
+}
+impl MovementPattern for ZigZagPattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        let (px, py) = get_perpendicular(*dx, *dy);
+        // Start half way through a leg, so the zig-zag is centred on the heading
+        let leg = ((world_time - self.started) / self.period + 0.5).floor() as i64;
+        let sideways = if leg % 2 == 0 {
+            self.sideways_speed
+        } else {
+            -self.sideways_speed
+        };
+        position.x += (*dx + px * sideways) * time_scale;
+        position.y += (*dy + py * sideways) * time_scale;
+        self.cycles.step(world_time)
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+}
+
+// Starts slow and speeds up along the heading, up to a limit
+#[derive(Clone)]
+pub struct AcceleratingPattern {
+    // Extra multiples of the heading gained per tick
+    pub acceleration: f64,
+    pub max_multiplier: f64,
+    started: f64,
+    cycles: CycleCounter,
+}
+impl AcceleratingPattern {
+    pub fn new(world_time: f64, acceleration: f64, max_multiplier: f64) -> Self {
+        AcceleratingPattern {
+            acceleration,
+            max_multiplier,
+            started: world_time,
+            cycles: CycleCounter::new(world_time),
+        }
+    }
+}
+impl MovementPattern for AcceleratingPattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        let multiplier = (self.acceleration * (world_time - self.started)).min(self.max_multiplier);
+        position.x += *dx * multiplier * time_scale;
+        position.y += *dy * multiplier * time_scale;
+        self.cycles.step(world_time)
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+}
+
+// Circles a point, which itself drifts along the heading
+#[derive(Clone)]
+pub struct OrbitPattern {
+    pub centre: Position,
+    pub radius: f64,
+    // Radians per tick
+    pub angular_speed: f64,
+    angle: f64,
+    cycles: CycleCounter,
+}
+impl OrbitPattern {
+    // Starts orbiting from the given position, with the centre directly to its left
+    pub fn new(world_time: f64, x: f64, y: f64, radius: f64, angular_speed: f64) -> Self {
+        OrbitPattern {
+            centre: Position { x: x - radius, y },
+            radius,
+            angular_speed,
+            angle: 0.0,
+            cycles: CycleCounter::new(world_time),
+        }
+    }
+}
+impl MovementPattern for OrbitPattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        self.centre.x += *dx * time_scale;
+        self.centre.y += *dy * time_scale;
+        self.angle += self.angular_speed * time_scale;
+        position.x = self.centre.x + self.radius * self.angle.cos();
+        position.y = self.centre.y + self.radius * self.angle.sin();
+        self.cycles.step(world_time)
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+}
+
+// Glides, bouncing off the sides and top of the arena so it can only leave through the bottom
+#[derive(Clone)]
+pub struct BouncePattern {
+    cycles: CycleCounter,
+}
+impl BouncePattern {
+    pub fn new(world_time: f64) -> Self {
+        BouncePattern {
+            cycles: CycleCounter::new(world_time),
+        }
+    }
+}
+impl MovementPattern for BouncePattern {
+    fn step(
+        &mut self,
+        dx: &mut f64,
+        dy: &mut f64,
+        position: &mut Position,
+        world_time: f64,
+        time_scale: f64,
+    ) -> bool {
+        position.x += *dx * time_scale;
+        position.y += *dy * time_scale;
+        if position.x < 0.0 {
+            position.x = -position.x;
+            *dx = dx.abs();
+        }
+        if position.x > GAME_SCREEN_WIDTH {
+            position.x = 2.0 * GAME_SCREEN_WIDTH - position.x;
+            *dx = -dx.abs();
+        }
+        if position.y < 0.0 && *dy < 0.0 {
+            position.y = -position.y;
+            *dy = -*dy;
+        }
+        self.cycles.step(world_time)
+    }
+    fn clone_box(&self) -> Box<dyn MovementPattern> {
+        Box::new(self.clone())
+    }
+}
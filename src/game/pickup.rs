@@ -0,0 +1,131 @@
+use super::*;
+
+const PICKUP_COLLECT_RADIUS: f64 = 12.0;
+// Measured in world time, so pickups don't run out while focusing
+const PICKUP_LIFETIME: f64 = 600.0;
+const SLOW_PICKUP_DURATION: u64 = 300;
+const BOMB_PICKUP_RADIUS: f64 = 100.0;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PickupKind {
+    ExtraLife,
+    Shield,
+    Bomb,
+    Slow,
+}
+
+impl PickupKind {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            PickupKind::ExtraLife => "extra life",
+            PickupKind::Shield => "shield",
+            PickupKind::Bomb => "bomb",
+            PickupKind::Slow => "slow",
+        }
+    }
+}
+
+pub const PICKUP_KINDS: [PickupKind; 4] = [
+    PickupKind::ExtraLife,
+    PickupKind::Shield,
+    PickupKind::Bomb,
+    PickupKind::Slow,
+];
+
+pub struct Pickup {
+    pub kind: PickupKind,
+    pub expires_at: f64,
+}
+
+impl Pickup {
+    pub fn new(game: &mut Game, x: f64, y: f64, kind: PickupKind) -> GameObjectId {
+        let id = game.create_game_object(x, y);
+        game.pickups.insert(
+            id,
+            Pickup {
+                kind,
+                expires_at: game.world_time + PICKUP_LIFETIME,
+            },
+        );
+        game.events.push(GameEvent::PickupSpawned(kind));
+        id
+    }
+    fn collect(game: &mut Game, kind: PickupKind) {
+        if let Some(player) = game.player.as_mut() {
+            game.events.push(GameEvent::PickupCollected(kind));
+            match kind {
+                PickupKind::ExtraLife => {
+                    player.lives = (player.lives + 1).min(MAX_LIVES);
+                }
+                PickupKind::Shield => {
+                    player.shield = true;
+                }
+                PickupKind::Slow => {
+                    player.slow_until = game.time + SLOW_PICKUP_DURATION;
+                }
+                PickupKind::Bomb => {
+                    let player_id = player.id;
+                    if let Some(position) = game.positions.get(&player_id) {
+                        let (x, y) = (position.x, position.y);
+                        BombBlast::detonate(game, x, y, BOMB_PICKUP_RADIUS);
+                    }
+                }
+            }
+        }
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_delete = Vec::new();
+        let mut collected = Vec::new();
+        let player_position = game
+            .player
+            .as_ref()
+            .and_then(|player| game.positions.get(&player.id));
+        for (id, pickup) in game.pickups.iter() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            let position = game.positions.get(id).expect("Pickup had no position");
+            if let Some(player_position) = player_position {
+                if position.is_closer_than(player_position, PICKUP_COLLECT_RADIUS) {
+                    to_delete.push(*id);
+                    collected.push(pickup.kind);
+                    continue;
+                }
+            }
+            if pickup.expires_at <= game.world_time {
+                to_delete.push(*id);
+            }
+        }
+        for id in to_delete {
+            game.delete(id);
+        }
+        for kind in collected {
+            Pickup::collect(game, kind);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawning_and_collecting_emit_events() {
+        let mut game = Game::with_seed(1);
+        Player::spawn(&mut game);
+        let player_id = game.player.as_ref().unwrap().id;
+        let position = *game.positions.get(&player_id).unwrap();
+        Pickup::new(&mut game, position.x, position.y, PickupKind::Shield);
+        assert!(matches!(
+            game.events.as_slice(),
+            [GameEvent::PickupSpawned(PickupKind::Shield)]
+        ));
+        game.events.clear();
+        Pickup::step(&mut game);
+        assert!(matches!(
+            game.events.as_slice(),
+            [GameEvent::PickupCollected(PickupKind::Shield)]
+        ));
+        assert!(game.player.as_ref().unwrap().shield);
+    }
+}
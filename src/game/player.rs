@@ -1,6 +1,24 @@
+use std::f64::consts::FRAC_1_SQRT_2;
+
 use super::*;
 
 const PLAYER_SPEED: f64 = 5.0;
+pub const MAX_LIVES: u8 = 9;
+pub const MAX_GRAZE_METER: u32 = 100;
+const DODGE_SPEED_MULTIPLIER: f64 = 3.0;
+const DODGE_DURATION: u64 = 8;
+const DODGE_INVINCIBILITY_TIME: u64 = 12;
+// Counted from the start of the dodge
+pub const DODGE_COOLDOWN: u64 = 90;
+// Must be a power of two so world time stays exact
+const FOCUS_TIME_SCALE: f64 = 0.5;
+const FOCUS_METER_DRAIN: u32 = 1;
+const STARTING_BOMBS: u8 = 1;
+pub const MAX_BOMBS: u8 = 3;
+const BOMB_EVERY_SCORE: u32 = 10000;
+const BOMB_RADIUS: f64 = 150.0;
+// How close the player's centre can get to a wall
+const PLAYER_WALL_RADIUS: f64 = 5.0;
 
 pub struct Player {
     pub id: GameObjectId,
@@ -8,10 +26,27 @@ pub struct Player {
     pub right: bool,
     pub up: bool,
     pub down: bool,
+    // The velocity the player is actually moving at
     pub dx: f64,
     pub dy: f64,
+    // The velocity the controls are asking for, which dx and dy move towards under the movement model
+    pub target_dx: f64,
+    pub target_dy: f64,
+    // Where the mouse is steering the player, which it stops on instead of passing
+    pub target: Option<Position>,
+    pub slow_walk: bool,
     pub invincibility_until: u64,
     pub lives: u8,
+    pub graze_meter: u32,
+    pub dodge_requested: bool,
+    pub dodge_until: u64,
+    pub dodge_ready_at: u64,
+    pub focus: bool,
+    pub shield: bool,
+    pub slow_until: u64,
+    pub bombs: u8,
+    pub bomb_requested: bool,
+    pub next_bomb_score: u32,
 }
 
 fn sticky_speed(negative: bool, positive: bool, previous: f64) -> f64 {
@@ -26,7 +61,41 @@ fn sticky_speed(negative: bool, positive: bool, previous: f64) -> f64 {
     }
     return 0.0;
 }
+// How the player's velocity follows the controls. Part of the simulation, so replays must use the same one.
+#[derive(Clone, Copy)]
+pub struct MovementModel {
+    // Whether moving diagonally with buttons is kept to the same speed as moving straight
+    pub normalize_diagonals: bool,
+    // How much the velocity can change each tick while moving, infinite to turn on a dime
+    pub acceleration: f64,
+    // How much the velocity drops each tick once the controls are let go, infinite to stop dead
+    pub friction: f64,
+    // Speed multiplier while the slow walk button is held
+    pub slow_walk_speed: f64,
+}
+
+impl Default for MovementModel {
+    fn default() -> Self {
+        MovementModel {
+            normalize_diagonals: true,
+            acceleration: f64::INFINITY,
+            friction: f64::INFINITY,
+            slow_walk_speed: 0.4,
+        }
+    }
+}
+
+// Undoes any diagonal or analog scaling, so sticky_speed carries on in the previous direction at full speed
+fn get_full_speed(speed: f64) -> f64 {
+    if speed == 0.0 {
+        0.0
+    } else {
+        speed.signum() * PLAYER_SPEED
+    }
+}
 const SPAWN_INVINCIBILITY_TIME: u64 = 300;
+// How far above the bottom of the arena the player appears
+const SPAWN_HEIGHT: f64 = 20.0;
 impl Player {
     pub fn new(game: &mut Game, x: f64, y: f64) {
         let id = game.create_game_object(x, y);
@@ -34,39 +103,299 @@ impl Player {
             id,
             dx: 0.0,
             dy: 0.0,
+            target_dx: 0.0,
+            target_dy: 0.0,
+            target: None,
+            slow_walk: false,
             up: false,
             down: false,
             left: false,
             right: false,
             invincibility_until: game.time + SPAWN_INVINCIBILITY_TIME,
             lives: 3,
+            graze_meter: 0,
+            dodge_requested: false,
+            dodge_until: 0,
+            dodge_ready_at: game.time,
+            focus: false,
+            shield: false,
+            slow_until: 0,
+            bombs: STARTING_BOMBS,
+            bomb_requested: false,
+            next_bomb_score: game.score() + BOMB_EVERY_SCORE,
         };
         game.player = Some(player);
     }
-    pub fn move_player(game: &mut Game) {
+    pub fn spawn(game: &mut Game) {
+        Player::new(
+            game,
+            GAME_SCREEN_WIDTH / 2.0,
+            GAME_SCREEN_HEIGHT - SPAWN_HEIGHT,
+        );
+    }
+    pub fn apply_input(game: &mut Game, input: &TickInput) {
+        if input.respawn {
+            Player::spawn(game);
+        }
+        if let Some(player) = game.player.as_mut() {
+            player.up = input.up;
+            player.down = input.down;
+            player.left = input.left;
+            player.right = input.right;
+            player.focus = input.focus;
+            player.slow_walk = input.slow_walk;
+            if input.dodge {
+                player.dodge_requested = true;
+            }
+            if input.bomb {
+                player.bomb_requested = true;
+            }
+            player.target = input.target;
+        }
+        match (input.target, input.analog) {
+            (Some(target), _) => Player::move_player_towards(game, &target),
+            (None, Some((x, y))) => Player::move_player_analog(game, x, y),
+            (None, None) => Player::move_player(game),
+        }
+    }
+    fn move_player(game: &mut Game) {
+        if let Some(player) = game.player.as_mut() {
+            player.target_dx =
+                sticky_speed(player.left, player.right, get_full_speed(player.target_dx));
+            player.target_dy =
+                sticky_speed(player.up, player.down, get_full_speed(player.target_dy));
+            if game.movement_model.normalize_diagonals
+                && player.target_dx != 0.0
+                && player.target_dy != 0.0
+            {
+                player.target_dx *= FRAC_1_SQRT_2;
+                player.target_dy *= FRAC_1_SQRT_2;
+            }
+        }
+    }
+    // Analog sticks set the speed directly, so a stick pushed halfway moves at half speed
+    fn move_player_analog(game: &mut Game, x: f64, y: f64) {
         if let Some(player) = game.player.as_mut() {
-            player.dx = sticky_speed(player.left, player.right, player.dx);
-            player.dy = sticky_speed(player.up, player.down, player.dy);
+            player.target_dx = x * PLAYER_SPEED;
+            player.target_dy = y * PLAYER_SPEED;
+        }
+    }
+    // Heads straight for the target, with step stopping the player on it rather than overshooting
+    fn move_player_towards(game: &mut Game, target: &Position) {
+        if let Some(player) = game.player.as_mut() {
+            if let Some(position) = game.positions.get(&player.id) {
+                let dx = target.x - position.x;
+                let dy = target.y - position.y;
+                let distance = dx.hypot(dy);
+                if distance == 0.0 {
+                    player.target_dx = 0.0;
+                    player.target_dy = 0.0;
+                    return;
+                }
+                player.target_dx = dx / distance * PLAYER_SPEED;
+                player.target_dy = dy / distance * PLAYER_SPEED;
+            }
+        }
+    }
+    // Moves the velocity towards what the controls ask for, as quickly as the movement model allows
+    fn accelerate(&mut self, model: &MovementModel) {
+        let multiplier = if self.slow_walk {
+            model.slow_walk_speed
+        } else {
+            1.0
+        };
+        let target_dx = self.target_dx * multiplier;
+        let target_dy = self.target_dy * multiplier;
+        let change_x = target_dx - self.dx;
+        let change_y = target_dy - self.dy;
+        let change = change_x.hypot(change_y);
+        let rate = if target_dx == 0.0 && target_dy == 0.0 {
+            model.friction
+        } else {
+            model.acceleration
+        };
+        if change <= rate {
+            self.dx = target_dx;
+            self.dy = target_dy;
+        } else {
+            self.dx += change_x / change * rate;
+            self.dy += change_y / change * rate;
+        }
+    }
+    pub fn is_dodging(&self, time: u64) -> bool {
+        self.dodge_until > time
+    }
+    fn start_dodge(&mut self, time: u64) {
+        self.dodge_requested = false;
+        // Dodging in place would just be free invincibility
+        if self.dodge_ready_at > time || (self.dx == 0.0 && self.dy == 0.0) {
+            return;
+        }
+        self.dodge_until = time + DODGE_DURATION;
+        self.dodge_ready_at = time + DODGE_COOLDOWN;
+        // Never shorten invincibility we already had from being hit or spawning
+        self.invincibility_until = self
+            .invincibility_until
+            .max(time + DODGE_INVINCIBILITY_TIME);
+    }
+    fn step_bombs(game: &mut Game) {
+        let score = game.score();
+        if let Some(player) = game.player.as_mut() {
+            if score >= player.next_bomb_score {
+                player.bombs = (player.bombs + 1).min(MAX_BOMBS);
+                player.next_bomb_score += BOMB_EVERY_SCORE;
+            }
+            if !player.bomb_requested {
+                return;
+            }
+            player.bomb_requested = false;
+            if player.bombs == 0 {
+                return;
+            }
+            player.bombs -= 1;
+            let player_id = player.id;
+            if let Some(position) = game.positions.get(&player_id) {
+                let (x, y) = (position.x, position.y);
+                BombBlast::detonate(game, x, y, BOMB_RADIUS);
+            }
+        }
+    }
+    // Each axis is blocked separately, so running into a wall at an angle slides along it
+    fn slide(position: &mut Position, dx: f64, dy: f64, arena: &Arena) {
+        // Something stuck in a wall (say, after spawning there) is let out freely
+        if arena.is_blocked(position, PLAYER_WALL_RADIUS) {
+            position.x = (position.x + dx).rem_euclid(GAME_SCREEN_WIDTH);
+            position.y = (position.y + dy).clamp(0.0, GAME_SCREEN_HEIGHT);
+            return;
+        }
+        let previous_x = position.x;
+        position.x += dx;
+        if position.x > GAME_SCREEN_WIDTH {
+            position.x -= GAME_SCREEN_WIDTH;
+        }
+        if position.x < 0.0 {
+            position.x += GAME_SCREEN_WIDTH;
+        }
+        if arena.is_blocked(position, PLAYER_WALL_RADIUS) {
+            position.x = previous_x;
+        }
+        let previous_y = position.y;
+        position.y = (position.y + dy).clamp(0.0, GAME_SCREEN_HEIGHT);
+        if arena.is_blocked(position, PLAYER_WALL_RADIUS) {
+            position.y = previous_y;
         }
     }
     pub fn step(game: &mut Game) {
-        if let Some(player) = game.player.as_ref() {
+        Player::step_bombs(game);
+        game.time_scale = 1.0;
+        if let Some(player) = game.player.as_mut() {
+            if player.slow_until > game.time {
+                game.time_scale = FOCUS_TIME_SCALE;
+            } else if player.focus && player.graze_meter >= FOCUS_METER_DRAIN {
+                player.graze_meter -= FOCUS_METER_DRAIN;
+                game.time_scale = FOCUS_TIME_SCALE;
+            }
+            player.accelerate(&game.movement_model);
+            if player.dodge_requested {
+                player.start_dodge(game.time);
+            }
+            let speed_multiplier = if player.is_dodging(game.time) {
+                DODGE_SPEED_MULTIPLIER
+            } else {
+                1.0
+            };
             if let Some(position) = game.positions.get_mut(&player.id) {
-                position.x += player.dx;
-                position.y += player.dy;
-                if position.x > GAME_SCREEN_WIDTH {
-                    position.x -= GAME_SCREEN_WIDTH;
-                }
-                if position.x < 0.0 {
-                    position.x += GAME_SCREEN_WIDTH;
-                }
-                if position.y > GAME_SCREEN_HEIGHT {
-                    position.y = GAME_SCREEN_HEIGHT;
+                let mut move_x = player.dx * speed_multiplier;
+                let mut move_y = player.dy * speed_multiplier;
+                // Capped after the dodge multiplier, so dodging at the target lands on it too
+                if let Some(target) = player.target {
+                    let distance = (target.x - position.x).hypot(target.y - position.y);
+                    let length = move_x.hypot(move_y);
+                    if length > distance {
+                        let scale = distance / length;
+                        (move_x, move_y) = (move_x * scale, move_y * scale);
+                        (player.dx, player.dy) = (player.dx * scale, player.dy * scale);
+                    }
                 }
-                if position.y < 0.0 {
-                    position.y = 0.0;
+                // Move in small steps so fast dodges can't jump over thin walls
+                let steps = (move_x.abs().max(move_y.abs()) / PLAYER_WALL_RADIUS).ceil();
+                for _ in 0..steps as u32 {
+                    Player::slide(position, move_x / steps, move_y / steps, &game.arena);
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawned_game() -> Game {
+        let mut game = Game::with_seed(1);
+        Player::spawn(&mut game);
+        game
+    }
+
+    #[test]
+    fn analog_input_scales_target_speed() {
+        let mut game = spawned_game();
+        let input = TickInput {
+            analog: Some((0.5, -0.25)),
+            ..TickInput::default()
+        };
+        Player::apply_input(&mut game, &input);
+        let player = game.player.as_ref().unwrap();
+        assert_eq!(player.target_dx, 0.5 * PLAYER_SPEED);
+        assert_eq!(player.target_dy, -0.25 * PLAYER_SPEED);
+    }
+
+    #[test]
+    fn analog_input_overrides_buttons() {
+        let mut game = spawned_game();
+        let input = TickInput {
+            left: true,
+            up: true,
+            analog: Some((1.0, 0.0)),
+            ..TickInput::default()
+        };
+        Player::apply_input(&mut game, &input);
+        let player = game.player.as_ref().unwrap();
+        assert_eq!(player.target_dx, PLAYER_SPEED);
+        assert_eq!(player.target_dy, 0.0);
+    }
+
+    #[test]
+    fn centred_stick_stops_the_player() {
+        let mut game = spawned_game();
+        let input = TickInput {
+            analog: Some((0.0, 0.0)),
+            ..TickInput::default()
+        };
+        Player::apply_input(&mut game, &input);
+        let player = game.player.as_ref().unwrap();
+        assert_eq!(player.target_dx, 0.0);
+        assert_eq!(player.target_dy, 0.0);
+    }
+
+    #[test]
+    fn dodging_at_the_target_stops_on_it() {
+        let mut game = spawned_game();
+        let id = game.player.as_ref().unwrap().id;
+        let start = *game.positions.get(&id).unwrap();
+        let target = Position {
+            x: start.x + PLAYER_SPEED * 2.0,
+            y: start.y,
+        };
+        let input = TickInput {
+            target: Some(target),
+            dodge: true,
+            ..TickInput::default()
+        };
+        Player::apply_input(&mut game, &input);
+        Player::step(&mut game);
+        let position = game.positions.get(&id).unwrap();
+        assert_eq!(position.x, target.x);
+        assert_eq!(position.y, target.y);
+    }
+}
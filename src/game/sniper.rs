@@ -0,0 +1,113 @@
+use super::*;
+
+// All sniper timings are in world time
+const SNIPER_TELEGRAPH_TIME: f64 = 90.0;
+const SNIPER_LOCK_TIME: f64 = 30.0;
+const SNIPER_FIRE_TIME: f64 = 8.0;
+// Long enough to cross the whole arena from any edge
+pub const LASER_LENGTH: f64 = GAME_SCREEN_WIDTH + GAME_SCREEN_HEIGHT;
+const LASER_RADIUS: f64 = 3.0;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SniperState {
+    // Following the player with its warning line
+    Aiming { until: f64 },
+    // The line no longer moves, but the shot hasn't happened yet
+    Locked { until: f64 },
+    Firing { laser: GameObjectId, until: f64 },
+}
+
+pub struct Sniper {
+    pub state: SniperState,
+    // Unit vector along the warning line
+    pub aim_dx: f64,
+    pub aim_dy: f64,
+}
+
+impl Sniper {
+    pub fn new(game: &mut Game, x: f64, y: f64) -> GameObjectId {
+        let id = game.create_game_object(x, y);
+        let mut sniper = Sniper {
+            state: SniperState::Aiming {
+                until: game.world_time + SNIPER_TELEGRAPH_TIME,
+            },
+            aim_dx: 0.0,
+            aim_dy: 1.0,
+        };
+        let target = game
+            .player
+            .as_ref()
+            .and_then(|player| game.positions.get(&player.id));
+        sniper.aim(&Position { x, y }, target);
+        game.snipers.insert(id, sniper);
+        id
+    }
+    fn aim(&mut self, position: &Position, target: Option<&Position>) {
+        if let Some(target) = target {
+            let dx = target.x - position.x;
+            let dy = target.y - position.y;
+            let length = dx.hypot(dy);
+            if length > 0.0 {
+                self.aim_dx = dx / length;
+                self.aim_dy = dy / length;
+            }
+        }
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_fire = Vec::new();
+        let mut to_delete = Vec::new();
+        let target = game
+            .player
+            .as_ref()
+            .and_then(|player| game.positions.get(&player.id));
+        for (id, sniper) in game.snipers.iter_mut() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            match sniper.state {
+                SniperState::Aiming { until } => {
+                    let position = game.positions.get(id).expect("Sniper had no position");
+                    sniper.aim(position, target);
+                    if until <= game.world_time {
+                        sniper.state = SniperState::Locked {
+                            until: game.world_time + SNIPER_LOCK_TIME,
+                        };
+                    }
+                }
+                SniperState::Locked { until } => {
+                    if until <= game.world_time {
+                        to_fire.push(*id);
+                    }
+                }
+                SniperState::Firing { laser, until } => {
+                    if until <= game.world_time {
+                        to_delete.push(laser);
+                        to_delete.push(*id);
+                    }
+                }
+            }
+        }
+        for id in to_fire {
+            Sniper::fire(game, id);
+        }
+        for id in to_delete {
+            game.delete(id);
+        }
+    }
+    fn fire(game: &mut Game, id: GameObjectId) {
+        let position = game.positions.get(&id).expect("Sniper had no position");
+        let (x, y) = (position.x, position.y);
+        let laser = game.create_game_object(x, y);
+        let sniper = game.snipers.get_mut(&id).expect("Sniper disappeared");
+        let (dx, dy) = (sniper.aim_dx * LASER_LENGTH, sniper.aim_dy * LASER_LENGTH);
+        sniper.state = SniperState::Firing {
+            laser,
+            until: game.world_time + SNIPER_FIRE_TIME,
+        };
+        Danger::new_line(game, laser, LASER_RADIUS, dx, dy);
+        // The laser is the sniper's only danger, so it carries what the sniper cost
+        if let Some(threat) = game.spawn_threats.remove(&id) {
+            game.spawn_threats.insert(laser, threat);
+        }
+    }
+}
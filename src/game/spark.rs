@@ -0,0 +1,33 @@
+use super::*;
+
+pub const SPARK_DURATION: u64 = 20;
+pub struct Spark {
+    pub expires_at: u64,
+}
+
+impl Spark {
+    pub fn new(game: &mut Game, x: f64, y: f64) -> GameObjectId {
+        let id = game.create_game_object(x, y);
+        game.sparks.insert(
+            id,
+            Spark {
+                expires_at: game.time + SPARK_DURATION,
+            },
+        );
+        id
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_delete = Vec::new();
+        for (id, spark) in game.sparks.iter() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            if spark.expires_at <= game.time {
+                to_delete.push(*id);
+            }
+        }
+        for id in to_delete {
+            game.delete(id);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+
+const FRAGMENT_RADIUS: f64 = 3.0;
+
+// A dasher that breaks into a fan of smaller, faster dashers
+pub struct Splitter {
+    // Splits once it has finished this many dashes...
+    pub split_after_dashes: u32,
+    // ...or once it gets this far down the screen, whichever is first
+    pub split_at_y: f64,
+    pub fragments: u32,
+    pub fragment_speed_multiplier: f64,
+    // Angle between the outermost fragments, in radians
+    pub spread: f64,
+}
+
+impl Splitter {
+    pub fn new(
+        game: &mut Game,
+        x: f64,
+        y: f64,
+        dx: f64,
+        dy: f64,
+        splitter: Splitter,
+    ) -> GameObjectId {
+        let id = Dasher::new(game, x, y, dx, dy);
+        game.splitters.insert(id, splitter);
+        id
+    }
+    // Directions the fragments will fly in, as unit vectors
+    pub fn get_fragment_directions(&self, dasher: &Dasher) -> Vec<(f64, f64)> {
+        let base_angle = if dasher.dx == 0.0 && dasher.dy == 0.0 {
+            std::f64::consts::FRAC_PI_2
+        } else {
+            dasher.dy.atan2(dasher.dx)
+        };
+        (0..self.fragments)
+            .map(|i| {
+                let offset = if self.fragments > 1 {
+                    self.spread * (i as f64 / (self.fragments - 1) as f64 - 0.5)
+                } else {
+                    0.0
+                };
+                let angle = base_angle + offset;
+                (angle.cos(), angle.sin())
+            })
+            .collect()
+    }
+    pub fn should_split(&self, dasher: &Dasher, position: &Position) -> bool {
+        dasher.dashes >= self.split_after_dashes || position.y >= self.split_at_y
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_split = Vec::new();
+        for (id, splitter) in game.splitters.iter() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            let dasher = game.dashers.get(id).expect("Splitter was not a dasher");
+            let position = game.positions.get(id).expect("Splitter had no position");
+            if splitter.should_split(dasher, position) {
+                let speed = dasher.dx.hypot(dasher.dy) * splitter.fragment_speed_multiplier;
+                let fragments: Vec<(f64, f64)> = splitter
+                    .get_fragment_directions(dasher)
+                    .into_iter()
+                    .map(|(x, y)| (x * speed, y * speed))
+                    .collect();
+                to_split.push((*id, position.x, position.y, fragments));
+            }
+        }
+        for (id, x, y, fragments) in to_split {
+            game.delete(id);
+            for (dx, dy) in fragments {
+                Dasher::new_with_radius(game, x, y, dx, dy, FRAGMENT_RADIUS);
+            }
+        }
+    }
+}
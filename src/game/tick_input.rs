@@ -0,0 +1,20 @@
+use super::*;
+
+// Everything the player asked for during one tick. Game::step applies it before anything else moves,
+// so a seed and the same sequence of these always plays out the same game.
+#[derive(Clone, Copy, Default)]
+pub struct TickInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    // From -1 to 1 on each axis, used instead of the direction buttons when set
+    pub analog: Option<(f64, f64)>,
+    // A point in the arena to head towards, used instead of any other movement when set
+    pub target: Option<Position>,
+    pub focus: bool,
+    pub slow_walk: bool,
+    pub dodge: bool,
+    pub bomb: bool,
+    pub respawn: bool,
+}
@@ -3,7 +3,16 @@ use std::ops::Mul;
 use derive_more::{Add, AddAssign, Sub, SubAssign};
 use rand::Rng;
 
-use super::{Dasher, Game, DASHER_MOVE_EVERY, DASHER_MOVE_FOR};
+use self::formations::spawn_formation;
+
+use super::{
+    AcceleratingPattern, Boss, BouncePattern, DashPattern, Dasher, Game, GameObjectId,
+    GlidePattern, Hunter, Mine, MovementPattern, OrbitPattern, Pickup, PickupKind, Sniper,
+    Splitter, WeavePattern, ZigZagPattern, Zone, DASHER_MOVE_EVERY, DASHER_MOVE_FOR, DASHER_RADIUS,
+    GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH, PICKUP_KINDS,
+};
+
+mod formations;
 
 #[derive(Eq, PartialEq, PartialOrd, Ord, Sub, SubAssign, Add, AddAssign)]
 pub struct Threat(i32);
@@ -28,9 +37,11 @@ impl Mul<i32> for Threat {
 const MINIMUM_THREAT_FOR_WAVE: Threat = Threat(400);
 const WAVE_SPAWN_WIDTH: u32 = 400;
 const MAX_DOWNWARDS_DASHER_WAVE_SIZE: u32 = 10;
-const DOWNWARD_DASHER_SPEED: f64 = (1.0 / DASHER_MOVE_FOR as f64) * DASHER_MOVE_EVERY as f64;
+const DOWNWARD_DASHER_SPEED: f64 = (1.0 / DASHER_MOVE_FOR) * DASHER_MOVE_EVERY;
 const DOWNWARD_DASHER_THREAT: Threat = Threat(60);
 const THREAT_PER_TICK: Threat = Threat(2);
+// Only part of a cleared danger's threat comes back, so clearing the screen buys some breathing room
+const CLEARED_THREAT_REFUND_PERCENT: i32 = 50;
 const MAX_BISHOP_WAVE_SIZE: u32 = 7;
 
 const INCREASE_THREAT_EVERY: u32 = 600;
@@ -40,49 +51,356 @@ fn get_speed(base_speed: f64, difficulty: u32) -> f64 {
     let increased_speed = difficulty / INCREASE_SPEED_EVERY;
     base_speed + increased_speed as f64
 }
-fn get_wave_size(max_size: u32) -> u32 {
-    let wave_size = rand::thread_rng().gen_range(0..max_size);
+fn get_wave_size(game: &mut Game, max_size: u32) -> u32 {
+    let wave_size = game.rng.gen_range(0..max_size);
     wave_size
 }
-pub fn spawn_downwards_dashers_with_parameters(
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SpawnSide {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+impl SpawnSide {
+    // Unit vector pointing into the arena from this side
+    fn get_inward_direction(self) -> (f64, f64) {
+        match self {
+            SpawnSide::Top => (0.0, 1.0),
+            SpawnSide::Bottom => (0.0, -1.0),
+            SpawnSide::Left => (1.0, 0.0),
+            SpawnSide::Right => (-1.0, 0.0),
+            SpawnSide::TopLeft => (SIN_ONE_EIGHTH, SIN_ONE_EIGHTH),
+            SpawnSide::TopRight => (-SIN_ONE_EIGHTH, SIN_ONE_EIGHTH),
+            SpawnSide::BottomLeft => (SIN_ONE_EIGHTH, -SIN_ONE_EIGHTH),
+            SpawnSide::BottomRight => (-SIN_ONE_EIGHTH, -SIN_ONE_EIGHTH),
+        }
+    }
+    // Dashers crossing the screen sideways would never leave if they wrapped
+    fn wraps(self) -> bool {
+        matches!(self, SpawnSide::Top | SpawnSide::Bottom)
+    }
+    fn get_spawn_point(self, game: &mut Game) -> (f64, f64) {
+        let along = game.rng.gen_range(0.0..1.0);
+        let corner_along = game.rng.gen_range(0.0..CORNER_SPAWN_SIZE);
+        match self {
+            SpawnSide::Top => (GAME_SCREEN_WIDTH * along, 0.0),
+            SpawnSide::Bottom => (GAME_SCREEN_WIDTH * along, GAME_SCREEN_HEIGHT),
+            SpawnSide::Left => (0.0, GAME_SCREEN_HEIGHT * along),
+            SpawnSide::Right => (GAME_SCREEN_WIDTH, GAME_SCREEN_HEIGHT * along),
+            SpawnSide::TopLeft => (corner_along, CORNER_SPAWN_SIZE - corner_along),
+            SpawnSide::TopRight => (
+                GAME_SCREEN_WIDTH - corner_along,
+                CORNER_SPAWN_SIZE - corner_along,
+            ),
+            SpawnSide::BottomLeft => (
+                corner_along,
+                GAME_SCREEN_HEIGHT - CORNER_SPAWN_SIZE + corner_along,
+            ),
+            SpawnSide::BottomRight => (
+                GAME_SCREEN_WIDTH - corner_along,
+                GAME_SCREEN_HEIGHT - CORNER_SPAWN_SIZE + corner_along,
+            ),
+        }
+    }
+}
+const SIN_ONE_EIGHTH: f64 = std::f64::consts::FRAC_1_SQRT_2;
+// Corner spawns are spread along a short diagonal across the corner
+const CORNER_SPAWN_SIZE: f64 = 60.0;
+pub fn spawn_dashers_with_parameters(
     game: &mut Game,
     max_wave_size: u32,
     speed: f64,
+    side: SpawnSide,
 ) -> Threat {
-    let wave_size = get_wave_size(max_wave_size);
+    let wave_size = get_wave_size(game, max_wave_size);
+    let (direction_x, direction_y) = side.get_inward_direction();
     for _ in 0u32..wave_size {
-        let x = rand::thread_rng().gen_range(0..WAVE_SPAWN_WIDTH);
-        Dasher::new(game, x as f64, 0.0, 0.0, speed);
+        let (x, y) = side.get_spawn_point(game);
+        let id = Dasher::new(game, x, y, direction_x * speed, direction_y * speed);
+        game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+        if let Some(dasher) = game.dashers.get_mut(&id) {
+            dasher.wraps = side.wraps();
+        }
     }
     return DOWNWARD_DASHER_THREAT * wave_size as i32;
 }
 pub fn spawn_downwards_dashers(game: &mut Game) -> Threat {
     let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty);
-    return spawn_downwards_dashers_with_parameters(game, MAX_DOWNWARDS_DASHER_WAVE_SIZE, speed);
+    return spawn_dashers_with_parameters(
+        game,
+        MAX_DOWNWARDS_DASHER_WAVE_SIZE,
+        speed,
+        SpawnSide::Top,
+    );
+}
+// Sides become available as difficulty goes up, with the player's own edge last
+const SIDE_SPAWNS_FROM: u32 = 2400;
+const CORNER_SPAWNS_FROM: u32 = 3600;
+const BOTTOM_SPAWNS_FROM: u32 = 4800;
+const MAX_EDGE_DASHER_WAVE_SIZE: u32 = 6;
+pub fn spawn_edge_dashers(game: &mut Game) -> Threat {
+    let difficulty = game.wave_spawner.current_difficulty;
+    let mut sides = vec![SpawnSide::Top];
+    if difficulty >= SIDE_SPAWNS_FROM {
+        sides.extend([SpawnSide::Left, SpawnSide::Right]);
+    }
+    if difficulty >= CORNER_SPAWNS_FROM {
+        sides.extend([SpawnSide::TopLeft, SpawnSide::TopRight]);
+    }
+    if difficulty >= BOTTOM_SPAWNS_FROM {
+        sides.extend([
+            SpawnSide::Bottom,
+            SpawnSide::BottomLeft,
+            SpawnSide::BottomRight,
+        ]);
+    }
+    let side = sides[game.rng.gen_range(0..sides.len())];
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, difficulty);
+    return spawn_dashers_with_parameters(game, MAX_EDGE_DASHER_WAVE_SIZE, speed, side);
 }
 const SPEEDSTER_SPEED_MULTIPLIER: f64 = 2.0;
 const MAX_SPEEDSTERS_WAVE_SIZE: u32 = 3;
 pub fn spawn_speedsters(game: &mut Game) -> Threat {
     let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty)
         * SPEEDSTER_SPEED_MULTIPLIER;
-    return spawn_downwards_dashers_with_parameters(game, MAX_SPEEDSTERS_WAVE_SIZE, speed);
+    return spawn_dashers_with_parameters(game, MAX_SPEEDSTERS_WAVE_SIZE, speed, SpawnSide::Top);
 }
 const EXTRA_BISHOP_EVERY: u32 = 600;
 const MAX_EXTRA_BISHOPS: u32 = 3;
 pub fn spawn_bishops(game: &mut Game) -> Threat {
     let extra_wave_size =
         MAX_EXTRA_BISHOPS.min(game.wave_spawner.current_difficulty / EXTRA_BISHOP_EVERY);
-    let wave_size = get_wave_size(MAX_BISHOP_WAVE_SIZE) + extra_wave_size;
+    let wave_size = get_wave_size(game, MAX_BISHOP_WAVE_SIZE) + extra_wave_size;
     let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty);
     for _ in 0u32..wave_size {
-        let x = rand::thread_rng().gen_range(0..WAVE_SPAWN_WIDTH);
-        let going_right = rand::thread_rng().gen_bool(0.5);
+        let x = game.rng.gen_range(0..WAVE_SPAWN_WIDTH);
+        let going_right = game.rng.gen_bool(0.5);
         let horizontal_speed = if going_right { speed } else { -speed } * 0.75;
-        Dasher::new(game, x as f64, 0.0, horizontal_speed, speed);
+        let id = Dasher::new(game, x as f64, 0.0, horizontal_speed, speed);
+        game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+    }
+    return DOWNWARD_DASHER_THREAT * wave_size as i32;
+}
+const MAX_HUNTER_WAVE_SIZE: u32 = 2;
+const HUNTER_THREAT: Threat = Threat(150);
+const HUNTER_SPEED_MULTIPLIER: f64 = 0.75;
+const HUNTER_LEAD_FACTOR: f64 = 1.0;
+const HUNTER_MAX_TURN: f64 = std::f64::consts::PI / 4.0;
+const HUNTER_AIMS: u32 = 6;
+pub fn spawn_hunters(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_HUNTER_WAVE_SIZE) + 1;
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty)
+        * HUNTER_SPEED_MULTIPLIER;
+    for _ in 0u32..wave_size {
+        let x = game.rng.gen_range(0..WAVE_SPAWN_WIDTH);
+        let id = Hunter::new(
+            game,
+            x as f64,
+            0.0,
+            speed,
+            HUNTER_LEAD_FACTOR,
+            HUNTER_MAX_TURN,
+            HUNTER_AIMS,
+        );
+        game.spawn_threats.insert(id, HUNTER_THREAT);
+    }
+    return HUNTER_THREAT * wave_size as i32;
+}
+const MAX_SNIPER_WAVE_SIZE: u32 = 2;
+const SNIPER_THREAT: Threat = Threat(200);
+// Snipers come in from the sides or the top, never the bottom where the player starts
+pub fn spawn_snipers(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_SNIPER_WAVE_SIZE) + 1;
+    for _ in 0u32..wave_size {
+        let along = game.rng.gen_range(0.0..1.0);
+        let (x, y) = match game.rng.gen_range(0..3) {
+            0 => (GAME_SCREEN_WIDTH * along, 0.0),
+            1 => (0.0, GAME_SCREEN_HEIGHT * along),
+            _ => (GAME_SCREEN_WIDTH, GAME_SCREEN_HEIGHT * along),
+        };
+        let id = Sniper::new(game, x, y);
+        game.spawn_threats.insert(id, SNIPER_THREAT);
+    }
+    return SNIPER_THREAT * wave_size as i32;
+}
+const MAX_SPLITTER_WAVE_SIZE: u32 = 3;
+// Includes the fragments it will break into
+const SPLITTER_THREAT: Threat = Threat(180);
+const SPLITTER_DASHES: u32 = 8;
+const SPLITTER_FRAGMENTS: u32 = 5;
+const SPLITTER_FRAGMENT_SPEED_MULTIPLIER: f64 = 1.5;
+const SPLITTER_SPREAD: f64 = std::f64::consts::FRAC_PI_2;
+pub fn spawn_splitters(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_SPLITTER_WAVE_SIZE) + 1;
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty);
+    for _ in 0u32..wave_size {
+        let x = game.rng.gen_range(0..WAVE_SPAWN_WIDTH);
+        let split_at_y = game
+            .rng
+            .gen_range(GAME_SCREEN_HEIGHT / 4.0..GAME_SCREEN_HEIGHT / 2.0);
+        let id = Splitter::new(
+            game,
+            x as f64,
+            0.0,
+            0.0,
+            speed,
+            Splitter {
+                split_after_dashes: SPLITTER_DASHES,
+                split_at_y,
+                fragments: SPLITTER_FRAGMENTS,
+                fragment_speed_multiplier: SPLITTER_FRAGMENT_SPEED_MULTIPLIER,
+                spread: SPLITTER_SPREAD,
+            },
+        );
+        game.spawn_threats.insert(id, SPLITTER_THREAT);
+    }
+    return SPLITTER_THREAT * wave_size as i32;
+}
+// Static hazards stay out of the top of the screen, where dashers appear
+const HAZARD_MIN_Y: f64 = GAME_SCREEN_HEIGHT / 4.0;
+const MAX_MINE_WAVE_SIZE: u32 = 4;
+const MINE_THREAT: Threat = Threat(80);
+pub fn spawn_mines(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_MINE_WAVE_SIZE) + 1;
+    for _ in 0u32..wave_size {
+        let x = game.rng.gen_range(0.0..GAME_SCREEN_WIDTH);
+        let y = game.rng.gen_range(HAZARD_MIN_Y..GAME_SCREEN_HEIGHT);
+        let id = Mine::new(game, x, y);
+        game.spawn_threats.insert(id, MINE_THREAT);
+    }
+    return MINE_THREAT * wave_size as i32;
+}
+const MAX_ZONE_WAVE_SIZE: u32 = 2;
+const ZONE_THREAT: Threat = Threat(150);
+const MIN_ZONE_RADIUS: f64 = 25.0;
+const MAX_ZONE_RADIUS: f64 = 50.0;
+const ZONE_WARNING_TIME: f64 = 90.0;
+const ZONE_DURATION: f64 = 300.0;
+pub fn spawn_zones(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_ZONE_WAVE_SIZE) + 1;
+    for _ in 0u32..wave_size {
+        let x = game.rng.gen_range(0.0..GAME_SCREEN_WIDTH);
+        let y = game.rng.gen_range(HAZARD_MIN_Y..GAME_SCREEN_HEIGHT);
+        let radius = game.rng.gen_range(MIN_ZONE_RADIUS..MAX_ZONE_RADIUS);
+        let id = Zone::new(game, x, y, radius, ZONE_WARNING_TIME, ZONE_DURATION);
+        game.spawn_threats.insert(id, ZONE_THREAT);
+    }
+    return ZONE_THREAT * wave_size as i32;
+}
+/* Continuous patterns move on every tick rather than for a third of the time,
+so they need a slower heading to make similar progress */
+const CONTINUOUS_SPEED_MULTIPLIER: f64 = DASHER_MOVE_FOR / (DASHER_MOVE_EVERY + DASHER_MOVE_FOR);
+const MAX_PATTERNED_WAVE_SIZE: u32 = 5;
+const WEAVE_AMPLITUDE: f64 = 30.0;
+const WEAVE_PERIOD: f64 = 90.0;
+const ZIG_ZAG_SIDEWAYS_SPEED: f64 = 2.0;
+const ZIG_ZAG_PERIOD: f64 = 40.0;
+const ACCELERATION: f64 = 0.02;
+const MAX_ACCELERATION_MULTIPLIER: f64 = 3.0;
+const ORBIT_RADIUS: f64 = 30.0;
+const ORBIT_ANGULAR_SPEED: f64 = 0.08;
+const BOUNCE_HORIZONTAL_SPEED: f64 = 0.75;
+fn spawn_patterned_dasher(game: &mut Game, x: f64, speed: f64) -> GameObjectId {
+    let world_time = game.world_time;
+    let mut dx = 0.0;
+    // Patterns that set their own position, or keep themselves on screen, don't wrap
+    let mut wraps = true;
+    let pattern: Box<dyn MovementPattern> = match game.rng.gen_range(0..6) {
+        0 => Box::new(GlidePattern::new(world_time)),
+        1 => Box::new(WeavePattern::new(world_time, WEAVE_AMPLITUDE, WEAVE_PERIOD)),
+        2 => Box::new(ZigZagPattern::new(
+            world_time,
+            ZIG_ZAG_SIDEWAYS_SPEED,
+            ZIG_ZAG_PERIOD,
+        )),
+        3 => Box::new(AcceleratingPattern::new(
+            world_time,
+            ACCELERATION,
+            MAX_ACCELERATION_MULTIPLIER,
+        )),
+        4 => {
+            wraps = false;
+            Box::new(OrbitPattern::new(
+                world_time,
+                x,
+                0.0,
+                ORBIT_RADIUS,
+                ORBIT_ANGULAR_SPEED,
+            ))
+        }
+        _ => {
+            wraps = false;
+            let going_right = game.rng.gen_bool(0.5);
+            dx = if going_right { speed } else { -speed } * BOUNCE_HORIZONTAL_SPEED;
+            Box::new(BouncePattern::new(world_time))
+        }
+    };
+    let id = Dasher::new_with_pattern(game, x, 0.0, dx, speed, DASHER_RADIUS, pattern);
+    if let Some(dasher) = game.dashers.get_mut(&id) {
+        dasher.wraps = wraps;
+    }
+    id
+}
+pub fn spawn_patterned_dashers(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_PATTERNED_WAVE_SIZE) + 1;
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty)
+        * CONTINUOUS_SPEED_MULTIPLIER;
+    for _ in 0u32..wave_size {
+        let x = game.rng.gen_range(0..WAVE_SPAWN_WIDTH);
+        let id = spawn_patterned_dasher(game, x as f64, speed);
+        game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+    }
+    return DOWNWARD_DASHER_THREAT * wave_size as i32;
+}
+// Staggered dashers each get their own rhythm, so a wave doesn't move in lockstep
+const MAX_STAGGERED_WAVE_SIZE: u32 = 8;
+const MIN_STAGGERED_MOVE_EVERY: f64 = 12.0;
+const MAX_STAGGERED_MOVE_EVERY: f64 = 32.0;
+const MIN_STAGGERED_MOVE_FOR: f64 = 6.0;
+const MAX_STAGGERED_MOVE_FOR: f64 = 14.0;
+pub fn spawn_staggered_dashers(game: &mut Game) -> Threat {
+    let wave_size = get_wave_size(game, MAX_STAGGERED_WAVE_SIZE) + 1;
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, game.wave_spawner.current_difficulty);
+    for _ in 0u32..wave_size {
+        let x = game.rng.gen_range(0..WAVE_SPAWN_WIDTH);
+        let move_every = game
+            .rng
+            .gen_range(MIN_STAGGERED_MOVE_EVERY..MAX_STAGGERED_MOVE_EVERY);
+        let move_for = game
+            .rng
+            .gen_range(MIN_STAGGERED_MOVE_FOR..MAX_STAGGERED_MOVE_FOR);
+        let phase = game.rng.gen_range(0.0..move_every + move_for);
+        // Keep each dash covering the same distance as a normal dasher's
+        let dash_speed = speed * DASHER_MOVE_FOR / move_for;
+        let pattern = DashPattern::with_timing(game.world_time, move_every, move_for, phase);
+        let id = Dasher::new_with_pattern(
+            game,
+            x as f64,
+            0.0,
+            0.0,
+            dash_speed,
+            DASHER_RADIUS,
+            Box::new(pattern),
+        );
+        game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
     }
     return DOWNWARD_DASHER_THREAT * wave_size as i32;
 }
 const BISHOP_WEIGHT: f64 = 0.25;
+const STAGGERED_WEIGHT: f64 = 0.3;
+const PATTERNED_WEIGHT: f64 = 0.3;
+const FORMATION_WEIGHT: f64 = 0.3;
+const EDGE_DASHER_WEIGHT: f64 = 0.3;
+const MINE_WEIGHT: f64 = 0.15;
+const ZONE_WEIGHT: f64 = 0.1;
+const SPLITTER_WEIGHT: f64 = 0.15;
+const SNIPER_WEIGHT: f64 = 0.15;
+const HUNTER_WEIGHT: f64 = 0.15;
 const DASHER_WEIGHT: f64 = 1.0;
 const SPEEDSTER_WEIGHT: f64 = 0.5;
 pub fn spawn_wave(game: &mut Game) -> Threat {
@@ -90,11 +408,21 @@ pub fn spawn_wave(game: &mut Game) -> Threat {
         (DASHER_WEIGHT, Box::new(spawn_downwards_dashers)),
         (SPEEDSTER_WEIGHT, Box::new(spawn_speedsters)),
         (BISHOP_WEIGHT, Box::new(spawn_bishops)),
+        (FORMATION_WEIGHT, Box::new(spawn_formation)),
+        (EDGE_DASHER_WEIGHT, Box::new(spawn_edge_dashers)),
+        (PATTERNED_WEIGHT, Box::new(spawn_patterned_dashers)),
+        (STAGGERED_WEIGHT, Box::new(spawn_staggered_dashers)),
+        (HUNTER_WEIGHT, Box::new(spawn_hunters)),
+        (SNIPER_WEIGHT, Box::new(spawn_snipers)),
+        (SPLITTER_WEIGHT, Box::new(spawn_splitters)),
+        (MINE_WEIGHT, Box::new(spawn_mines)),
+        (ZONE_WEIGHT, Box::new(spawn_zones)),
     ];
     let mut total_prob: f64 = possibilities.iter().map(|(p, _)| p).sum();
     for (p, f) in possibilities.iter() {
-        let chance = p / total_prob;
-        if rand::thread_rng().gen_bool(chance) {
+        // Rounding in the running total can leave the last weight just over what's left
+        let chance = (p / total_prob).min(1.0);
+        if game.rng.gen_bool(chance) {
             return f(game);
         }
         total_prob -= p;
@@ -102,19 +430,90 @@ pub fn spawn_wave(game: &mut Game) -> Threat {
     return possibilities.last().expect("No wave possibilities").1(game);
 }
 
+// Pickups have a budget of their own, so a busy wave doesn't starve the player of them
+const PICKUP_BUDGET_PER_TICK: u32 = 1;
+// Keep pickups away from the top, where dashers appear
+const PICKUP_MIN_Y: f64 = GAME_SCREEN_HEIGHT / 4.0;
+fn get_pickup_cost(kind: PickupKind) -> u32 {
+    match kind {
+        PickupKind::ExtraLife => 3000,
+        PickupKind::Shield => 1500,
+        PickupKind::Bomb => 1500,
+        PickupKind::Slow => 1000,
+    }
+}
+pub fn spawn_pickup(game: &mut Game, kind: PickupKind) {
+    let x = game.rng.gen_range(0..WAVE_SPAWN_WIDTH);
+    let y = game.rng.gen_range(PICKUP_MIN_Y..GAME_SCREEN_HEIGHT);
+    Pickup::new(game, x as f64, y, kind);
+}
+
 const STARTING_DIFFICULTY: u32 = DIFFICULTY_PER_TICK * 600;
+const BOSS_EVERY: u32 = DIFFICULTY_PER_TICK * 3600;
 pub struct WaveSpawner {
     threat_needed: Threat,
     pub current_difficulty: u32,
+    pickup_budget: u32,
+    next_pickup: Option<PickupKind>,
+    next_boss_difficulty: u32,
+    bosses_spawned: u32,
 }
 impl WaveSpawner {
     pub fn new() -> Self {
         WaveSpawner {
             threat_needed: Threat(0),
             current_difficulty: STARTING_DIFFICULTY,
+            pickup_budget: 0,
+            next_pickup: None,
+            next_boss_difficulty: STARTING_DIFFICULTY + BOSS_EVERY,
+            bosses_spawned: 0,
+        }
+    }
+    // Anything the budget didn't pay for, like a boss's attacks or a splitter's fragments, refunds nothing
+    pub fn refund_cleared(game: &mut Game, cleared: &[GameObjectId]) {
+        let threat: i32 = cleared
+            .iter()
+            .filter_map(|id| game.spawn_threats.get(id))
+            .map(|threat| threat.0)
+            .sum();
+        game.wave_spawner.threat_needed += Threat(threat * CLEARED_THREAT_REFUND_PERCENT / 100);
+    }
+    fn step_pickups(game: &mut Game) {
+        if game.player.is_none() {
+            return;
+        }
+        let next_pickup = match game.wave_spawner.next_pickup {
+            Some(kind) => kind,
+            None => {
+                let kind = PICKUP_KINDS[game.rng.gen_range(0..PICKUP_KINDS.len())];
+                game.wave_spawner.next_pickup = Some(kind);
+                kind
+            }
+        };
+        let cost = get_pickup_cost(next_pickup);
+        if game.wave_spawner.pickup_budget >= cost {
+            spawn_pickup(game, next_pickup);
+            game.wave_spawner.pickup_budget -= cost;
+            game.wave_spawner.next_pickup = None;
         }
+        game.wave_spawner.pickup_budget += PICKUP_BUDGET_PER_TICK;
+    }
+    pub fn is_paused(game: &Game) -> bool {
+        !game.bosses.is_empty()
     }
     pub fn step(game: &mut Game) {
+        // Everything waits while a boss is being fought
+        if WaveSpawner::is_paused(game) {
+            return;
+        }
+        if game.player.is_some()
+            && game.wave_spawner.current_difficulty >= game.wave_spawner.next_boss_difficulty
+        {
+            Boss::new(game, game.wave_spawner.bosses_spawned);
+            game.wave_spawner.bosses_spawned += 1;
+            game.wave_spawner.next_boss_difficulty += BOSS_EVERY;
+            return;
+        }
         if game.wave_spawner.threat_needed > MINIMUM_THREAT_FOR_WAVE {
             let wave_threat = spawn_wave(game);
             game.wave_spawner.threat_needed -= wave_threat;
@@ -124,5 +523,25 @@ impl WaveSpawner {
         if game.player.is_some() {
             game.wave_spawner.current_difficulty += DIFFICULTY_PER_TICK;
         }
+        WaveSpawner::step_pickups(game);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clearing_refunds_only_what_the_budget_paid_for() {
+        let mut game = Game::with_seed(1);
+        let spent = spawn_mines(&mut game);
+        // Like a boss's attack, which never came out of the budget
+        Dasher::new(&mut game, 0.0, 0.0, 0.0, 1.0);
+        let cleared: Vec<GameObjectId> = game.positions.keys().copied().collect();
+        WaveSpawner::refund_cleared(&mut game, &cleared);
+        assert!(
+            game.wave_spawner.threat_needed
+                == Threat(spent.0 * CLEARED_THREAT_REFUND_PERCENT / 100)
+        );
     }
 }
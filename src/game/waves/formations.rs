@@ -0,0 +1,130 @@
+use rand::Rng;
+
+use super::{get_speed, Threat, DOWNWARD_DASHER_SPEED, DOWNWARD_DASHER_THREAT};
+use crate::game::{Dasher, Game, GAME_SCREEN_WIDTH};
+
+/* Formations spawn dashers as coordinated groups rather than at independent random x positions.
+Anything starting above the screen is fine, as dashers are only removed once below it. */
+const FORMATION_SPACING: f64 = 20.0;
+
+// Walls get fewer gaps as difficulty goes up, but always keep at least one
+const MAX_WALL_GAPS: u32 = 3;
+const REMOVE_WALL_GAP_EVERY: u32 = 2400;
+const WALL_GAP_WIDTH: f64 = 60.0;
+const WALL_SPEED_MULTIPLIER: f64 = 0.5;
+pub fn spawn_wall(game: &mut Game) -> Threat {
+    let difficulty = game.wave_spawner.current_difficulty;
+    let gap_count = MAX_WALL_GAPS
+        .saturating_sub(difficulty / REMOVE_WALL_GAP_EVERY)
+        .max(1);
+    let gaps: Vec<f64> = (0..gap_count)
+        .map(|_| game.rng.gen_range(0.0..GAME_SCREEN_WIDTH - WALL_GAP_WIDTH))
+        .collect();
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, difficulty) * WALL_SPEED_MULTIPLIER;
+    let mut count = 0;
+    let mut x = FORMATION_SPACING / 2.0;
+    while x < GAME_SCREEN_WIDTH {
+        let in_gap = gaps
+            .iter()
+            .any(|gap_start| x >= *gap_start && x <= gap_start + WALL_GAP_WIDTH);
+        if !in_gap {
+            let id = Dasher::new(game, x, 0.0, 0.0, speed);
+            game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+            count += 1;
+        }
+        x += FORMATION_SPACING;
+    }
+    DOWNWARD_DASHER_THREAT * count
+}
+
+const MIN_V_ARM_LENGTH: u32 = 3;
+const MAX_EXTRA_V_ARM_LENGTH: u32 = 3;
+const EXTRA_V_ARM_EVERY: u32 = 1200;
+/* A V has its point leading, so it splits around the player.
+An inverted V (a chevron) leads with its arms, so it closes in around them instead. */
+pub fn spawn_v(game: &mut Game, inverted: bool) -> Threat {
+    let difficulty = game.wave_spawner.current_difficulty;
+    let arm_length = MIN_V_ARM_LENGTH + MAX_EXTRA_V_ARM_LENGTH.min(difficulty / EXTRA_V_ARM_EVERY);
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, difficulty);
+    let centre = game.rng.gen_range(0.0..GAME_SCREEN_WIDTH);
+    let arm_height = arm_length as f64 * FORMATION_SPACING;
+    let mut count = 1;
+    let tip_y = if inverted { -arm_height } else { 0.0 };
+    let id = Dasher::new(game, centre, tip_y, 0.0, speed);
+    game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+    for i in 1..=arm_length {
+        let offset = i as f64 * FORMATION_SPACING;
+        let y = if inverted {
+            offset - arm_height
+        } else {
+            -offset
+        };
+        for x in [centre - offset, centre + offset] {
+            let id = Dasher::new(game, x, y, 0.0, speed);
+            game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+        }
+        count += 2;
+    }
+    DOWNWARD_DASHER_THREAT * count
+}
+pub fn spawn_v_shape(game: &mut Game) -> Threat {
+    spawn_v(game, false)
+}
+pub fn spawn_chevron(game: &mut Game) -> Threat {
+    spawn_v(game, true)
+}
+
+const MIN_STAIRCASE_LENGTH: u32 = 5;
+const MAX_EXTRA_STAIRCASE_LENGTH: u32 = 5;
+const EXTRA_STAIR_EVERY: u32 = 900;
+pub fn spawn_staircase(game: &mut Game) -> Threat {
+    let difficulty = game.wave_spawner.current_difficulty;
+    let length =
+        MIN_STAIRCASE_LENGTH + MAX_EXTRA_STAIRCASE_LENGTH.min(difficulty / EXTRA_STAIR_EVERY);
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, difficulty);
+    let start = game.rng.gen_range(0.0..GAME_SCREEN_WIDTH);
+    let direction = if game.rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+    for i in 0..length {
+        let offset = i as f64 * FORMATION_SPACING;
+        let id = Dasher::new(game, start + offset * direction, -offset, 0.0, speed);
+        game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+    }
+    DOWNWARD_DASHER_THREAT * length as i32
+}
+
+const MIN_MIRRORED_PAIRS: u32 = 2;
+const MAX_EXTRA_MIRRORED_PAIRS: u32 = 3;
+const EXTRA_MIRRORED_PAIR_EVERY: u32 = 1200;
+const MIRRORED_HORIZONTAL_SPEED: f64 = 0.75;
+// Pairs mirrored about the middle of the screen, each crossing towards the other side
+pub fn spawn_mirrored_pairs(game: &mut Game) -> Threat {
+    let difficulty = game.wave_spawner.current_difficulty;
+    let pairs =
+        MIN_MIRRORED_PAIRS + MAX_EXTRA_MIRRORED_PAIRS.min(difficulty / EXTRA_MIRRORED_PAIR_EVERY);
+    let speed = get_speed(DOWNWARD_DASHER_SPEED, difficulty);
+    for i in 0..pairs {
+        let x = game.rng.gen_range(0.0..GAME_SCREEN_WIDTH / 2.0);
+        let y = -(i as f64) * FORMATION_SPACING;
+        let horizontal_speed = speed * MIRRORED_HORIZONTAL_SPEED;
+        for (x, dx) in [
+            (x, horizontal_speed),
+            (GAME_SCREEN_WIDTH - x, -horizontal_speed),
+        ] {
+            let id = Dasher::new(game, x, y, dx, speed);
+            game.spawn_threats.insert(id, DOWNWARD_DASHER_THREAT);
+        }
+    }
+    DOWNWARD_DASHER_THREAT * (pairs * 2) as i32
+}
+
+pub fn spawn_formation(game: &mut Game) -> Threat {
+    let formations: [fn(&mut Game) -> Threat; 5] = [
+        spawn_wall,
+        spawn_v_shape,
+        spawn_chevron,
+        spawn_staircase,
+        spawn_mirrored_pairs,
+    ];
+    let choice = game.rng.gen_range(0..formations.len());
+    formations[choice](game)
+}
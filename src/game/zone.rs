@@ -0,0 +1,57 @@
+use super::*;
+
+// A lingering area that hurts the player for as long as they stand in it
+pub struct Zone {
+    pub radius: f64,
+    // Before this the zone is only a warning, and can't hurt
+    pub active_at: f64,
+    pub expires_at: f64,
+}
+
+impl Zone {
+    pub fn new(
+        game: &mut Game,
+        x: f64,
+        y: f64,
+        radius: f64,
+        warning_time: f64,
+        duration: f64,
+    ) -> GameObjectId {
+        let id = game.create_game_object(x, y);
+        game.zones.insert(
+            id,
+            Zone {
+                radius,
+                active_at: game.world_time + warning_time,
+                expires_at: game.world_time + warning_time + duration,
+            },
+        );
+        id
+    }
+    pub fn is_active(&self, world_time: f64) -> bool {
+        self.active_at <= world_time
+    }
+    pub fn step(game: &mut Game) {
+        let mut to_activate = Vec::new();
+        let mut to_delete = Vec::new();
+        for (id, zone) in game.zones.iter() {
+            if game.deleted.contains_key(id) {
+                continue;
+            }
+            if zone.expires_at <= game.world_time {
+                to_delete.push(*id);
+            } else if zone.is_active(game.world_time) && !game.dangers.contains_key(id) {
+                to_activate.push((*id, zone.radius));
+            }
+        }
+        for (id, radius) in to_activate {
+            Danger::new(game, id, radius);
+            if let Some(danger) = game.dangers.get_mut(&id) {
+                danger.consumed_on_hit = false;
+            }
+        }
+        for id in to_delete {
+            game.delete(id);
+        }
+    }
+}
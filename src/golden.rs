@@ -0,0 +1,149 @@
+use std::{fs, path::Path, process};
+
+use crate::{
+    drawing::{for_each_row, render_offscreen, Camera, Overlays, OFFSCREEN_FORMAT},
+    game::{Arena, Game, Player, TickInput, GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH},
+};
+use sdl2::surface::Surface;
+
+const GOLDEN_DIRECTORY: &str = "golden";
+
+// A game state reached by running a seed for some ticks with nobody at the controls
+struct Snapshot {
+    name: &'static str,
+    seed: u64,
+    ticks: u32,
+    level: Option<&'static str>,
+}
+
+const SNAPSHOTS: [Snapshot; 4] = [
+    Snapshot {
+        name: "start",
+        seed: 1,
+        ticks: 0,
+        level: None,
+    },
+    Snapshot {
+        name: "first_waves",
+        seed: 1,
+        ticks: 600,
+        level: None,
+    },
+    Snapshot {
+        name: "pillars",
+        seed: 2,
+        ticks: 900,
+        level: Some("levels/pillars.txt"),
+    },
+    Snapshot {
+        name: "crowded",
+        seed: 3,
+        ticks: 5000,
+        level: None,
+    },
+];
+
+impl Snapshot {
+    fn get_path(&self, suffix: &str) -> String {
+        format!("{}/{}{}.bmp", GOLDEN_DIRECTORY, self.name, suffix)
+    }
+    fn render(&self) -> Result<Surface<'static>, String> {
+        let mut game = Game::with_seed(self.seed);
+        if let Some(level) = self.level {
+            game.arena = Arena::load(level)?;
+        }
+        Player::spawn(&mut game);
+        for _ in 0..self.ticks {
+            game.step(&TickInput::default());
+        }
+        render_offscreen(
+            &game,
+            &Camera::new(),
+            &Overlays::default(),
+            1.0,
+            GAME_SCREEN_WIDTH as u32,
+            GAME_SCREEN_HEIGHT as u32,
+        )
+    }
+    // Returns how many bytes of pixel data differ from the golden image
+    fn compare(&self, rendered: &Surface) -> Result<usize, String> {
+        let path = self.get_path("");
+        if !Path::new(&path).exists() {
+            return Err(format!(
+                "no golden image at {}, create it with check-golden --update",
+                path
+            ));
+        }
+        let golden = Surface::load_bmp(path)?.convert_format(OFFSCREEN_FORMAT)?;
+        if golden.size() != rendered.size() {
+            return Err(format!(
+                "golden image is {:?} but rendered {:?}",
+                golden.size(),
+                rendered.size()
+            ));
+        }
+        let mut golden_rows = Vec::new();
+        for_each_row(&golden, |row| golden_rows.push(row.to_vec()));
+        let mut differences = 0;
+        let mut golden_rows = golden_rows.iter();
+        for_each_row(rendered, |row| {
+            let golden_row = golden_rows.next().expect("Golden image ran out of rows");
+            differences += row
+                .iter()
+                .zip(golden_row.iter())
+                .filter(|(a, b)| a != b)
+                .count();
+        });
+        Ok(differences)
+    }
+    // Writes the rendered image next to the golden one if they differ, so the two can be looked at side by side
+    fn check(&self) -> Result<usize, String> {
+        let rendered = self.render()?;
+        let differences = self.compare(&rendered)?;
+        if differences > 0 {
+            rendered.save_bmp(self.get_path(".actual"))?;
+        }
+        Ok(differences)
+    }
+    fn update(&self) -> Result<(), String> {
+        self.render()?.save_bmp(self.get_path(""))
+    }
+}
+
+/* Renders each snapshot and compares it against its image in the golden directory, writing a .actual.bmp
+next to any that differ. With --update the golden images are rewritten instead, for when a rendering change is
+intended. Exits with a failure if anything differs or can't be checked. */
+pub fn check_golden(args: &[String]) {
+    let update = args.iter().any(|arg| arg == "--update");
+    if update {
+        fs::create_dir_all(GOLDEN_DIRECTORY).expect("Could not create the golden directory");
+    }
+    let mut failures = 0;
+    for snapshot in SNAPSHOTS.iter() {
+        let result = if update {
+            snapshot.update().map(|_| 0)
+        } else {
+            snapshot.check()
+        };
+        match result {
+            Ok(0) if update => println!("{}: updated", snapshot.name),
+            Ok(0) => println!("{}: ok", snapshot.name),
+            Ok(differences) => {
+                println!(
+                    "{}: {} bytes differ, see {}",
+                    snapshot.name,
+                    differences,
+                    snapshot.get_path(".actual")
+                );
+                failures += 1;
+            }
+            Err(error) => {
+                println!("{}: {}", snapshot.name, error);
+                failures += 1;
+            }
+        }
+    }
+    if failures > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,652 @@
+use std::collections::{HashMap, HashSet};
+
+use sdl2::{
+    controller::{Axis, Button, GameController},
+    event::Event,
+    keyboard::Keycode,
+    GameControllerSubsystem,
+};
+
+use crate::{
+    drawing::{Camera, Viewport},
+    game::TickInput,
+    settings::Settings,
+};
+
+#[derive(Hash, Eq, PartialEq, Debug, Copy, Clone)]
+pub enum Action {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Dodge,
+    Focus,
+    SlowWalk,
+    Bomb,
+    Respawn,
+    Reset,
+    Pause,
+    Rebind,
+    Fullscreen,
+    ZoomIn,
+    ZoomOut,
+    FreeCamera,
+    Screenshot,
+}
+
+// In the order the rebinding screen asks for them
+pub const ACTIONS: [Action; 17] = [
+    Action::MoveUp,
+    Action::MoveDown,
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::Dodge,
+    Action::Focus,
+    Action::SlowWalk,
+    Action::Bomb,
+    Action::Respawn,
+    Action::Reset,
+    Action::Pause,
+    Action::Rebind,
+    Action::Fullscreen,
+    Action::ZoomIn,
+    Action::ZoomOut,
+    Action::FreeCamera,
+    Action::Screenshot,
+];
+
+impl Action {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Dodge => "dodge",
+            Action::Focus => "focus",
+            Action::SlowWalk => "slow_walk",
+            Action::Bomb => "bomb",
+            Action::Respawn => "respawn",
+            Action::Reset => "reset",
+            Action::Pause => "pause",
+            Action::Rebind => "rebind",
+            Action::Fullscreen => "fullscreen",
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::FreeCamera => "free_camera",
+            Action::Screenshot => "screenshot",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<Action> {
+        ACTIONS
+            .iter()
+            .copied()
+            .find(|action| action.get_name() == name)
+    }
+}
+
+#[derive(Eq, PartialEq, Debug, Copy, Clone)]
+pub enum ControlMode {
+    // Keys, D-pad and stick
+    Buttons,
+    // The player follows the mouse cursor, with buttons still used for everything else
+    Mouse,
+}
+
+impl ControlMode {
+    pub fn get_name(self) -> &'static str {
+        match self {
+            ControlMode::Buttons => "buttons",
+            ControlMode::Mouse => "mouse",
+        }
+    }
+    pub fn from_name(name: &str) -> Option<ControlMode> {
+        [ControlMode::Buttons, ControlMode::Mouse]
+            .into_iter()
+            .find(|mode| mode.get_name() == name)
+    }
+}
+
+// Keys are matched by what they type rather than where they are, so rebinding suits any layout
+#[derive(Clone)]
+pub struct Bindings<K> {
+    keys: Vec<(K, Action)>,
+}
+
+impl<K: Copy + PartialEq> Bindings<K> {
+    pub fn empty() -> Self {
+        Bindings { keys: Vec::new() }
+    }
+    pub fn get_action(&self, key: K) -> Option<Action> {
+        self.keys
+            .iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+    pub fn get_keys(&self, action: Action) -> Vec<K> {
+        self.keys
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| *key)
+            .collect()
+    }
+    // A key only ever does one thing, so binding it takes it away from anything else
+    pub fn bind(&mut self, key: K, action: Action) {
+        self.keys.retain(|(bound, _)| *bound != key);
+        self.keys.push((key, action));
+    }
+    pub fn unbind_action(&mut self, action: Action) {
+        self.keys.retain(|(_, bound)| *bound != action);
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &(K, Action)> {
+        self.keys.iter()
+    }
+}
+
+impl Default for Bindings<Keycode> {
+    fn default() -> Self {
+        let mut bindings = Bindings::empty();
+        bindings.bind(Keycode::W, Action::MoveUp);
+        bindings.bind(Keycode::Up, Action::MoveUp);
+        bindings.bind(Keycode::S, Action::MoveDown);
+        bindings.bind(Keycode::Down, Action::MoveDown);
+        bindings.bind(Keycode::A, Action::MoveLeft);
+        bindings.bind(Keycode::Left, Action::MoveLeft);
+        bindings.bind(Keycode::D, Action::MoveRight);
+        bindings.bind(Keycode::Right, Action::MoveRight);
+        bindings.bind(Keycode::Space, Action::Dodge);
+        bindings.bind(Keycode::LShift, Action::Focus);
+        bindings.bind(Keycode::LCtrl, Action::SlowWalk);
+        bindings.bind(Keycode::B, Action::Bomb);
+        bindings.bind(Keycode::P, Action::Respawn);
+        bindings.bind(Keycode::R, Action::Reset);
+        bindings.bind(Keycode::Escape, Action::Pause);
+        bindings.bind(Keycode::F1, Action::Rebind);
+        bindings.bind(Keycode::F11, Action::Fullscreen);
+        bindings.bind(Keycode::Equals, Action::ZoomIn);
+        bindings.bind(Keycode::Minus, Action::ZoomOut);
+        bindings.bind(Keycode::F3, Action::FreeCamera);
+        bindings.bind(Keycode::F12, Action::Screenshot);
+        bindings
+    }
+}
+
+impl Default for Bindings<Button> {
+    fn default() -> Self {
+        let mut bindings = Bindings::empty();
+        bindings.bind(Button::DPadUp, Action::MoveUp);
+        bindings.bind(Button::DPadDown, Action::MoveDown);
+        bindings.bind(Button::DPadLeft, Action::MoveLeft);
+        bindings.bind(Button::DPadRight, Action::MoveRight);
+        bindings.bind(Button::A, Action::Dodge);
+        bindings.bind(Button::LeftShoulder, Action::Focus);
+        bindings.bind(Button::RightShoulder, Action::Focus);
+        bindings.bind(Button::X, Action::SlowWalk);
+        bindings.bind(Button::B, Action::Bomb);
+        bindings.bind(Button::Back, Action::Respawn);
+        bindings.bind(Button::Start, Action::Pause);
+        bindings
+    }
+}
+
+// Escape always leaves the rebinding screen and backspace keeps an action's current keys
+const REBIND_CANCEL_KEY: Keycode = Keycode::Escape;
+const REBIND_SKIP_KEY: Keycode = Keycode::Backspace;
+
+// The largest value SDL reports for a stick axis
+const AXIS_MAX: f64 = 32767.0;
+
+// An open controller, with where its left stick is pointing from -1 to 1 on each axis
+struct Controller {
+    controller: GameController,
+    stick_x: f64,
+    stick_y: f64,
+    // Kept apart from the keyboard's, so unplugging only lets go of what this controller was holding
+    held_buttons: HashSet<Button>,
+}
+
+impl Controller {
+    // Controllers can have their own bindings, chosen by the name SDL gives them
+    fn get_bindings<'a>(&self, settings: &'a Settings) -> &'a Bindings<Button> {
+        settings
+            .controller_button_bindings
+            .get(&self.controller.name())
+            .unwrap_or(&settings.button_bindings)
+    }
+}
+
+// Scaled so movement starts from zero at the deadzone's edge, or None while the stick is inside it
+fn scale_stick(x: f64, y: f64, deadzone: f64) -> Option<(f64, f64)> {
+    let magnitude = x.hypot(y);
+    if magnitude <= deadzone {
+        return None;
+    }
+    let scaled = ((magnitude - deadzone) / (1.0 - deadzone)).min(1.0);
+    Some((x / magnitude * scaled, y / magnitude * scaled))
+}
+
+pub struct InputState {
+    /* Held actions are worked out from the keys and buttons themselves,
+    so letting go of one key leaves its action held while another key bound to it is still down */
+    held_keys: HashSet<Keycode>,
+    // Actions pressed since the last tick, so quick taps aren't lost
+    pressed: HashSet<Action>,
+    // Which of ACTIONS the rebinding screen is waiting on a key for
+    pub rebinding: Option<usize>,
+    // By SDL instance id, which stays the same for as long as the controller is plugged in
+    controllers: HashMap<u32, Controller>,
+    // In window coordinates, once the mouse has moved over the window
+    mouse: Option<(i32, i32)>,
+}
+
+impl InputState {
+    pub fn new() -> Self {
+        InputState {
+            held_keys: HashSet::new(),
+            pressed: HashSet::new(),
+            rebinding: None,
+            controllers: HashMap::new(),
+            mouse: None,
+        }
+    }
+    pub fn is_held(&self, action: Action, settings: &Settings) -> bool {
+        self.held_keys
+            .iter()
+            .any(|key| settings.key_bindings.get_action(*key) == Some(action))
+            || self.controllers.values().any(|controller| {
+                let bindings = controller.get_bindings(settings);
+                controller
+                    .held_buttons
+                    .iter()
+                    .any(|button| bindings.get_action(*button) == Some(action))
+            })
+    }
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+    // Where the first stick outside the deadzone is pointing
+    pub fn get_analog_move(&self, deadzone: f64) -> Option<(f64, f64)> {
+        self.controllers
+            .values()
+            .find_map(|controller| scale_stick(controller.stick_x, controller.stick_y, deadzone))
+    }
+    // Everything the game needs to know about this tick's input, taken all at once
+    pub fn get_tick_input(
+        &self,
+        settings: &Settings,
+        viewport: &Viewport,
+        camera: &Camera,
+    ) -> TickInput {
+        let target = match (settings.control_mode, self.mouse) {
+            (ControlMode::Mouse, Some((x, y))) => {
+                Some(camera.view_to_world(&viewport.window_to_arena(x, y)))
+            }
+            _ => None,
+        };
+        TickInput {
+            up: self.is_held(Action::MoveUp, settings),
+            down: self.is_held(Action::MoveDown, settings),
+            left: self.is_held(Action::MoveLeft, settings),
+            right: self.is_held(Action::MoveRight, settings),
+            analog: self.get_analog_move(settings.controller_deadzone),
+            target,
+            focus: self.is_held(Action::Focus, settings),
+            slow_walk: self.is_held(Action::SlowWalk, settings),
+            dodge: self.was_pressed(Action::Dodge),
+            bomb: self.was_pressed(Action::Bomb),
+            respawn: self.was_pressed(Action::Respawn),
+        }
+    }
+    // For actions handled outside the game, which shouldn't wait for a tick to be cleared
+    pub fn take_pressed(&mut self, action: Action) -> bool {
+        self.pressed.remove(&action)
+    }
+    pub fn end_tick(&mut self) {
+        self.pressed.clear();
+    }
+    pub fn start_rebinding(&mut self) {
+        // Anything held would otherwise stay held until pressed again under its new key
+        self.held_keys.clear();
+        for controller in self.controllers.values_mut() {
+            controller.held_buttons.clear();
+        }
+        self.rebinding = Some(0);
+    }
+    // Returns whether the settings were changed and should be saved
+    pub fn handle_event(
+        &mut self,
+        event: &Event,
+        settings: &mut Settings,
+        controller_subsystem: &GameControllerSubsystem,
+    ) -> bool {
+        match event {
+            Event::KeyDown {
+                keycode: Some(keycode),
+                repeat: false,
+                ..
+            } => {
+                if let Some(index) = self.rebinding {
+                    return self.rebind(index, *keycode, settings);
+                }
+                self.held_keys.insert(*keycode);
+                if let Some(action) = settings.key_bindings.get_action(*keycode) {
+                    self.pressed.insert(action);
+                }
+            }
+            Event::KeyUp {
+                keycode: Some(keycode),
+                ..
+            } => {
+                self.held_keys.remove(keycode);
+            }
+            // Sent for controllers already plugged in at startup as well as new ones
+            Event::ControllerDeviceAdded { which, .. } => match controller_subsystem.open(*which) {
+                Ok(controller) => {
+                    self.controllers.insert(
+                        controller.instance_id(),
+                        Controller {
+                            controller,
+                            stick_x: 0.0,
+                            stick_y: 0.0,
+                            held_buttons: HashSet::new(),
+                        },
+                    );
+                }
+                Err(error) => eprintln!("Could not open controller {}: {}", which, error),
+            },
+            Event::ControllerDeviceRemoved { which, .. } => {
+                // Its buttons will never be released now, so whatever it held goes with it
+                self.controllers.remove(which);
+            }
+            Event::ControllerAxisMotion {
+                which, axis, value, ..
+            } => {
+                if let Some(controller) = self.controllers.get_mut(which) {
+                    let value = (*value as f64 / AXIS_MAX).clamp(-1.0, 1.0);
+                    match axis {
+                        Axis::LeftX => controller.stick_x = value,
+                        Axis::LeftY => controller.stick_y = value,
+                        _ => {}
+                    }
+                }
+            }
+            Event::ControllerButtonDown { which, button, .. } => {
+                if self.rebinding.is_some() {
+                    return false;
+                }
+                if let Some(controller) = self.controllers.get_mut(which) {
+                    controller.held_buttons.insert(*button);
+                    if let Some(action) = controller.get_bindings(settings).get_action(*button) {
+                        self.pressed.insert(action);
+                    }
+                }
+            }
+            Event::ControllerButtonUp { which, button, .. } => {
+                if let Some(controller) = self.controllers.get_mut(which) {
+                    controller.held_buttons.remove(button);
+                }
+            }
+            Event::MouseMotion { x, y, .. } => {
+                self.mouse = Some((*x, *y));
+            }
+            _ => {}
+        }
+        false
+    }
+    fn rebind(&mut self, index: usize, keycode: Keycode, settings: &mut Settings) -> bool {
+        if keycode == REBIND_CANCEL_KEY {
+            self.rebinding = None;
+            return false;
+        }
+        if keycode != REBIND_SKIP_KEY {
+            let action = ACTIONS[index];
+            settings.key_bindings.unbind_action(action);
+            settings.key_bindings.bind(keycode, action);
+        }
+        if index + 1 < ACTIONS.len() {
+            self.rebinding = Some(index + 1);
+        } else {
+            self.rebinding = None;
+        }
+        keycode != REBIND_SKIP_KEY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        f64::consts::FRAC_1_SQRT_2,
+        sync::{Mutex, MutexGuard},
+    };
+
+    use sdl2::{keyboard::Mod, sys, EventPump, Sdl};
+
+    use super::*;
+
+    const DEADZONE: f64 = 0.2;
+
+    fn assert_close(actual: (f64, f64), expected: (f64, f64)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn stick_inside_deadzone_is_ignored() {
+        assert_eq!(scale_stick(0.0, 0.0, DEADZONE), None);
+        assert_eq!(scale_stick(0.1, -0.1, DEADZONE), None);
+        assert_eq!(scale_stick(0.0, DEADZONE, DEADZONE), None);
+    }
+
+    #[test]
+    fn stick_movement_starts_from_zero_at_deadzone_edge() {
+        let (x, y) = scale_stick(0.21, 0.0, DEADZONE).unwrap();
+        assert!(x > 0.0 && x < 0.02);
+        assert_eq!(y, 0.0);
+    }
+
+    #[test]
+    fn stick_halfway_past_deadzone_moves_at_half_speed() {
+        assert_close(scale_stick(0.6, 0.0, DEADZONE).unwrap(), (0.5, 0.0));
+        assert_close(scale_stick(0.0, -0.6, DEADZONE).unwrap(), (0.0, -0.5));
+    }
+
+    #[test]
+    fn stick_keeps_its_direction() {
+        let (x, y) = scale_stick(0.3, 0.4, DEADZONE).unwrap();
+        assert!((y / x - 0.4 / 0.3).abs() < 1e-9);
+        assert!((x.hypot(y) - 0.375).abs() < 1e-9);
+    }
+
+    #[test]
+    fn stick_pushed_into_corner_is_capped_at_full_speed() {
+        let (x, y) = scale_stick(1.0, 1.0, DEADZONE).unwrap();
+        assert!((x.hypot(y) - 1.0).abs() < 1e-9);
+        assert_close((x, y), (FRAC_1_SQRT_2, FRAC_1_SQRT_2));
+    }
+
+    #[test]
+    fn no_deadzone_passes_stick_through() {
+        assert_close(scale_stick(0.25, -0.5, 0.0).unwrap(), (0.25, -0.5));
+    }
+
+    fn key_event(keycode: Keycode, down: bool) -> Event {
+        if down {
+            Event::KeyDown {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(keycode),
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }
+        } else {
+            Event::KeyUp {
+                timestamp: 0,
+                window_id: 0,
+                keycode: Some(keycode),
+                scancode: None,
+                keymod: Mod::NOMOD,
+                repeat: false,
+            }
+        }
+    }
+
+    // Only one SDL context can be alive at a time, so tests that start SDL take turns
+    static SDL_LOCK: Mutex<()> = Mutex::new(());
+
+    // Drives an InputState from controllers plugged in through SDL's virtual joystick API
+    struct VirtualControllers {
+        // Dropped in this order, so controllers are closed before SDL quits
+        input: InputState,
+        settings: Settings,
+        event_pump: EventPump,
+        controller_subsystem: GameControllerSubsystem,
+        _sdl: Sdl,
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl VirtualControllers {
+        fn new() -> Self {
+            let lock = SDL_LOCK.lock().unwrap_or_else(|error| error.into_inner());
+            let sdl = sdl2::init().unwrap();
+            let controller_subsystem = sdl.game_controller().unwrap();
+            let event_pump = sdl.event_pump().unwrap();
+            VirtualControllers {
+                input: InputState::new(),
+                settings: Settings::new(),
+                event_pump,
+                controller_subsystem,
+                _sdl: sdl,
+                _lock: lock,
+            }
+        }
+        fn send(&mut self, event: &Event) {
+            self.input
+                .handle_event(event, &mut self.settings, &self.controller_subsystem);
+        }
+        fn handle_events(&mut self) {
+            self.controller_subsystem.update();
+            for event in self.event_pump.poll_iter() {
+                self.input
+                    .handle_event(&event, &mut self.settings, &self.controller_subsystem);
+            }
+        }
+        // Returns the device index to unplug it with, and the instance id its events come from
+        fn plug_in(&mut self) -> (i32, u32) {
+            let index = unsafe {
+                sys::SDL_JoystickAttachVirtual(
+                    sys::SDL_JoystickType::SDL_JOYSTICK_TYPE_GAMECONTROLLER,
+                    sys::SDL_GameControllerAxis::SDL_CONTROLLER_AXIS_MAX as i32,
+                    sys::SDL_GameControllerButton::SDL_CONTROLLER_BUTTON_MAX as i32,
+                    0,
+                )
+            };
+            assert!(index >= 0, "{}", sdl2::get_error());
+            let which = unsafe { sys::SDL_JoystickGetDeviceInstanceID(index) } as u32;
+            self.handle_events();
+            (index, which)
+        }
+        fn unplug(&mut self, index: i32) {
+            unsafe { sys::SDL_JoystickDetachVirtual(index) };
+            self.handle_events();
+        }
+        fn get_joystick(which: u32) -> *mut sys::SDL_Joystick {
+            unsafe { sys::SDL_JoystickFromInstanceID(which as i32) }
+        }
+        fn set_button(&mut self, which: u32, button: Button, down: bool) {
+            let joystick = VirtualControllers::get_joystick(which);
+            unsafe { sys::SDL_JoystickSetVirtualButton(joystick, button as i32, down as u8) };
+            self.handle_events();
+        }
+        fn set_stick(&mut self, which: u32, x: f64, y: f64) {
+            let joystick = VirtualControllers::get_joystick(which);
+            for (axis, value) in [(Axis::LeftX, x), (Axis::LeftY, y)] {
+                let value = (value * AXIS_MAX) as i16;
+                unsafe { sys::SDL_JoystickSetVirtualAxis(joystick, axis as i32, value) };
+            }
+            self.handle_events();
+        }
+    }
+
+    #[test]
+    fn releasing_one_of_two_keys_keeps_the_action_held() {
+        let mut sdl = VirtualControllers::new();
+        for (keycode, down, held) in [
+            (Keycode::W, true, true),
+            (Keycode::Up, true, true),
+            (Keycode::W, false, true),
+            (Keycode::Up, false, false),
+        ] {
+            sdl.send(&key_event(keycode, down));
+            assert_eq!(sdl.input.is_held(Action::MoveUp, &sdl.settings), held);
+        }
+    }
+
+    #[test]
+    fn releasing_one_of_two_buttons_keeps_the_action_held() {
+        let mut sdl = VirtualControllers::new();
+        let (_, which) = sdl.plug_in();
+        for (button, down, held) in [
+            (Button::LeftShoulder, true, true),
+            (Button::RightShoulder, true, true),
+            (Button::LeftShoulder, false, true),
+            (Button::RightShoulder, false, false),
+        ] {
+            sdl.set_button(which, button, down);
+            assert_eq!(sdl.input.is_held(Action::Focus, &sdl.settings), held);
+        }
+    }
+
+    #[test]
+    fn unplugging_a_controller_lets_go_of_its_buttons() {
+        let mut sdl = VirtualControllers::new();
+        let (index, which) = sdl.plug_in();
+        assert!(sdl.input.controllers.contains_key(&which));
+        sdl.set_button(which, Button::DPadUp, true);
+        sdl.send(&key_event(Keycode::W, true));
+        sdl.unplug(index);
+        assert!(sdl.input.controllers.is_empty());
+        // The keyboard is still holding up on its own
+        assert!(sdl.input.is_held(Action::MoveUp, &sdl.settings));
+        sdl.send(&key_event(Keycode::W, false));
+        assert!(!sdl.input.is_held(Action::MoveUp, &sdl.settings));
+        let (_, which) = sdl.plug_in();
+        sdl.set_button(which, Button::DPadUp, true);
+        assert!(sdl.input.is_held(Action::MoveUp, &sdl.settings));
+    }
+
+    #[test]
+    fn stick_events_go_through_the_deadzone() {
+        let mut sdl = VirtualControllers::new();
+        let (_, which) = sdl.plug_in();
+        sdl.set_stick(which, 0.1, 0.1);
+        assert_eq!(sdl.input.get_analog_move(DEADZONE), None);
+        sdl.set_stick(which, 0.6, 0.0);
+        let (x, y) = sdl.input.get_analog_move(DEADZONE).unwrap();
+        // SDL's axes are whole numbers, so the stick only gets close to where it was put
+        assert!((x - 0.5).abs() < 1e-3 && y == 0.0);
+    }
+
+    #[test]
+    fn controllers_use_their_own_bindings() {
+        let mut sdl = VirtualControllers::new();
+        let (_, which) = sdl.plug_in();
+        let name = sdl.input.controllers[&which].controller.name();
+        let mut bindings = Bindings::empty();
+        bindings.bind(Button::A, Action::Bomb);
+        sdl.settings
+            .controller_button_bindings
+            .insert(name, bindings);
+        sdl.set_button(which, Button::A, true);
+        assert!(sdl.input.is_held(Action::Bomb, &sdl.settings));
+        assert!(!sdl.input.is_held(Action::Dodge, &sdl.settings));
+        assert!(sdl.input.take_pressed(Action::Bomb));
+        // Only that controller's own bindings apply to it
+        sdl.set_button(which, Button::B, true);
+        assert!(!sdl.input.take_pressed(Action::Bomb));
+    }
+}
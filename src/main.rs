@@ -2,168 +2,263 @@
 
 use std::{
     thread::sleep,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
-use game::{Player, GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH};
-use sdl2::{event::WindowEvent, keyboard::Keycode};
+use drawing::{render_offscreen, Camera, Notice, Overlays, Viewport};
+use game::{Arena, Game, Player, GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH};
+use input::{Action, InputState};
+use replay::{Replay, REPLAY_PATH};
+use sdl2::{event::WindowEvent, video::FullscreenType};
+use settings::{Settings, SETTINGS_PATH};
 
 mod drawing;
 mod game;
+mod golden;
+mod input;
+mod render_replay;
+mod replay;
+mod settings;
 
 const WINDOW_WIDTH: u32 = 400;
 const WINDOW_HEIGHT: u32 = 400;
 const TICK_TIME: Duration = Duration::from_millis(1000 / 60);
+// After a long stall, give up on catching up rather than spending every frame on ticks
+const MAX_TICKS_PER_FRAME: u32 = 5;
 fn main() {
-    let mut game = game::Game::new();
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("check-golden") => return golden::check_golden(&args[2..]),
+        Some("render-replay") => return render_replay::render_replay(&args[2..]),
+        _ => {}
+    }
+    // Otherwise an optional level file can be given as the first argument
+    let level = args.get(1).cloned();
+    let arena = match level.as_ref() {
+        Some(path) => Arena::load(path).unwrap_or_else(|error| panic!("{}", error)),
+        None => Arena::empty(),
+    };
+    let mut settings = Settings::load(SETTINGS_PATH).unwrap_or_else(|error| {
+        eprintln!("{}, using the default settings", error);
+        Settings::new()
+    });
+    let mut input = InputState::new();
+    let mut paused = false;
+    let mut game = start_game(&arena, &settings);
     let sdl2_system = sdl2::init().expect("Couldn't initialise SDL");
     let video_subsystem = sdl2_system.video().expect("No video");
+    // Kept alive so controller events keep arriving
+    let controller_subsystem = sdl2_system
+        .game_controller()
+        .expect("Could not initialise game controllers");
     let mut window_builder =
         video_subsystem.window("PREDICT THEM OR DIE", WINDOW_WIDTH, WINDOW_HEIGHT);
-    let window = window_builder
-        .opengl()
-        .build()
-        .expect("Could not create window!");
-    let mut canvas = window
-        .into_canvas()
-        .accelerated()
-        .build()
-        .expect("Could not create canvas!");
+    window_builder.opengl().resizable();
+    if settings.fullscreen {
+        window_builder.fullscreen_desktop();
+    }
+    let window = window_builder.build().expect("Could not create window!");
+    let mut canvas_builder = window.into_canvas().accelerated();
+    if settings.vsync {
+        canvas_builder = canvas_builder.present_vsync();
+    }
+    let mut canvas = canvas_builder.build().expect("Could not create canvas!");
     let mut event_pump = sdl2_system
         .event_pump()
         .expect("Could not obtain event pump!");
-    Player::new(
-        &mut game,
-        GAME_SCREEN_WIDTH as f64 / 2.0,
-        GAME_SCREEN_HEIGHT - 20.0,
-    );
+    let mut replay = start_replay(&game, &settings, &level);
+    let mut camera = Camera::new();
+    let mut notice = Notice::new();
+    // Real time not yet simulated, which is always less than a tick after the ticks for a frame are run
+    let mut unsimulated = Duration::ZERO;
+    let mut last_frame = Instant::now();
     'main: loop {
-        let tick_start = Instant::now();
-        game.step();
-        drawing::draw(&game, &mut canvas, 0, 0);
+        let frame_start = Instant::now();
+        let frame_time = frame_start - last_frame;
+        last_frame = frame_start;
         event_pump.pump_events();
         for event in event_pump.poll_iter() {
             match event {
                 sdl2::event::Event::Quit { .. } => break 'main,
-                sdl2::event::Event::KeyUp {
-                    timestamp: _,
-                    window_id: _,
-                    keycode,
-                    scancode: _,
-                    keymod: _,
-                    repeat: _,
-                } => match keycode {
-                    Some(Keycode::W) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.up = false;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::A) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.left = false;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::S) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.down = false;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::D) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.right = false;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::P) => {
-                        Player::new(
-                            &mut game,
-                            GAME_SCREEN_WIDTH as f64 / 2.0,
-                            GAME_SCREEN_HEIGHT - 20.0,
-                        );
-                    }
-                    Some(Keycode::R) => {
-                        game = game::Game::new();
-                        Player::new(
-                            &mut game,
-                            GAME_SCREEN_WIDTH as f64 / 2.0,
-                            GAME_SCREEN_HEIGHT - 20.0,
-                        );
-                    }
-
-                    _ => {}
-                },
-                sdl2::event::Event::KeyDown {
-                    timestamp: _,
-                    window_id: _,
-                    keycode,
-                    scancode: _,
-                    keymod: _,
-                    repeat: _,
-                } => match keycode {
-                    Some(Keycode::W) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.up = true;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::A) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.left = true;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::S) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.down = true;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    Some(Keycode::D) => {
-                        if let Some(player) = game.player.as_mut() {
-                            player.right = true;
-                            Player::move_player(&mut game);
-                        }
-                    }
-                    _ => {}
-                },
                 sdl2::event::Event::Window {
-                    timestamp: _,
-                    window_id: _,
-                    win_event,
-                } => match win_event {
-                    WindowEvent::Close { .. } => {
-                        break 'main;
-                    }
-                    _ => {}
-                },
+                    win_event: WindowEvent::Close,
+                    ..
+                } => break 'main,
                 _ => {}
             }
+            if input.handle_event(&event, &mut settings, &controller_subsystem) {
+                if let Err(error) = settings.save(SETTINGS_PATH) {
+                    eprintln!("{}", error);
+                }
+            }
+        }
+        if input.take_pressed(Action::Pause) {
+            paused = !paused;
+        }
+        if input.take_pressed(Action::Rebind) {
+            input.start_rebinding();
+        }
+        if input.take_pressed(Action::Fullscreen) {
+            settings.fullscreen = !settings.fullscreen;
+            let fullscreen_type = if settings.fullscreen {
+                FullscreenType::Desktop
+            } else {
+                FullscreenType::Off
+            };
+            if let Err(error) = canvas.window_mut().set_fullscreen(fullscreen_type) {
+                eprintln!("Could not change fullscreen: {}", error);
+            }
+            if let Err(error) = settings.save(SETTINGS_PATH) {
+                eprintln!("{}", error);
+            }
+        }
+        if input.take_pressed(Action::ZoomIn) {
+            camera.zoom_in();
+        }
+        if input.take_pressed(Action::ZoomOut) {
+            camera.zoom_out();
+        }
+        if input.take_pressed(Action::FreeCamera) {
+            camera.toggle_free();
+        }
+        if input.take_pressed(Action::Screenshot) {
+            take_screenshot(&game, &camera);
+        }
+        if input.take_pressed(Action::Reset) {
+            save_replay(&replay);
+            game = start_game(&arena, &settings);
+            replay = start_replay(&game, &settings, &level);
+        }
+        if paused || input.rebinding.is_some() {
+            // No ticks run, so drop game actions now rather than have them all fire on the first tick back
+            input.end_tick();
+        } else {
+            // Time spent paused is never simulated, which also holds the interpolation where it was
+            unsimulated += frame_time;
+        }
+        // Worked out every frame, since the window can be resized or go fullscreen at any time
+        let viewport = Viewport::fit(
+            canvas.output_size().expect("Could not get output size"),
+            canvas.window().size(),
+            settings.integer_scaling,
+        );
+        let mut ticks = 0;
+        while unsimulated >= TICK_TIME {
+            if ticks == MAX_TICKS_PER_FRAME {
+                unsimulated = Duration::ZERO;
+                break;
+            }
+            // Input is read before stepping so it affects this tick rather than the next
+            let mut tick_input = input.get_tick_input(&settings, &viewport, &camera);
+            // The free camera borrows the movement controls
+            if camera.free {
+                (
+                    tick_input.up,
+                    tick_input.down,
+                    tick_input.left,
+                    tick_input.right,
+                ) = (false, false, false, false);
+                (tick_input.analog, tick_input.target) = (None, None);
+            }
+            game.step(&tick_input);
+            camera.handle_events(&game.events);
+            notice.handle_events(&game.events);
+            replay.inputs.push(tick_input);
+            input.end_tick();
+            unsimulated -= TICK_TIME;
+            ticks += 1;
+        }
+        // Draw objects part of the way from their last position, by how far we are into the next tick
+        let interpolation = unsimulated.as_secs_f64() / TICK_TIME.as_secs_f64();
+        if camera.free {
+            let pan_x = input.is_held(Action::MoveRight, &settings) as i32
+                - input.is_held(Action::MoveLeft, &settings) as i32;
+            let pan_y = input.is_held(Action::MoveDown, &settings) as i32
+                - input.is_held(Action::MoveUp, &settings) as i32;
+            camera.pan(pan_x as f64, pan_y as f64, frame_time.as_secs_f64());
+        }
+        camera.update(&game, interpolation, frame_time.as_secs_f64());
+        notice.update(frame_time.as_secs_f64());
+        viewport.apply(&mut canvas);
+        drawing::draw(
+            &game,
+            &mut canvas,
+            &viewport,
+            &camera,
+            &Overlays::default(),
+            interpolation,
+        );
+        notice.draw(&mut canvas);
+        if let Some(index) = input.rebinding {
+            drawing::draw_rebinding(&mut canvas, &settings.key_bindings, index);
+        } else if paused {
+            drawing::draw_paused(&mut canvas);
         }
+        canvas.present();
         if let Some(player) = game.player.as_ref() {
             canvas
                 .window_mut()
                 .set_title(&format!(
                     "PREDICT THEM AND LIVE - {} chances remain (score {})",
                     player.lives,
-                    game.wave_spawner.current_difficulty
+                    game.score()
                 ))
                 .expect("Could not set title");
         } else {
             canvas
                 .window_mut()
-                .set_title(&format!(
-                    "DEATH (score {})",
-                    game.wave_spawner.current_difficulty
-                ))
+                .set_title(&format!("DEATH (score {})", game.score()))
                 .expect("Could not set title");
         }
-        let time_passed = tick_start.elapsed();
-        if time_passed < TICK_TIME {
-            let remaining = TICK_TIME - time_passed;
-            sleep(remaining);
+        // Vsync already waits in present, otherwise wait for the next tick to be due
+        if !settings.vsync {
+            let next_tick = TICK_TIME.saturating_sub(unsimulated);
+            sleep(next_tick.saturating_sub(frame_start.elapsed()));
         }
     }
+    save_replay(&replay);
+}
+
+fn start_game(arena: &Arena, settings: &Settings) -> Game {
+    let mut game = Game::new();
+    game.arena = arena.clone();
+    game.movement_model = settings.movement_model;
+    Player::spawn(&mut game);
+    game
+}
+
+fn start_replay(game: &Game, settings: &Settings, level: &Option<String>) -> Replay {
+    Replay::new(
+        game.seed,
+        settings.control_mode,
+        level.clone(),
+        game.movement_model,
+    )
+}
+
+// Rendered offscreen at the arena's own size, so screenshots look the same whatever the window is doing
+fn take_screenshot(game: &Game, camera: &Camera) {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let path = format!("screenshot-{}-{}.bmp", seconds, game.time);
+    let result = render_offscreen(
+        game,
+        camera,
+        &Overlays::default(),
+        1.0,
+        GAME_SCREEN_WIDTH as u32,
+        GAME_SCREEN_HEIGHT as u32,
+    )
+    .and_then(|surface| surface.save_bmp(&path));
+    match result {
+        Ok(()) => println!("Saved {}", path),
+        Err(error) => eprintln!("Could not save screenshot {}: {}", path, error),
+    }
+}
+
+fn save_replay(replay: &Replay) {
+    if let Err(error) = replay.save(REPLAY_PATH) {
+        eprintln!("{}", error);
+    }
 }
@@ -0,0 +1,167 @@
+use std::{
+    fs,
+    io::{self, Write},
+    process,
+};
+
+use crate::{
+    drawing::{for_each_row, render_offscreen, Camera, Overlays},
+    game::{Game, GAME_SCREEN_HEIGHT, GAME_SCREEN_WIDTH},
+    replay::Replay,
+};
+
+const TICKS_PER_SECOND: u32 = 60;
+const DEFAULT_OUTPUT_DIRECTORY: &str = "frames";
+
+const USAGE: &str = "usage: render-replay <replay> [--out <directory> | --raw] [--every <n>] [--scale <n>] [--paths] [--hitboxes]
+  --out       write numbered BMPs to this directory (default frames)
+  --raw       write raw RGB24 frames to stdout instead, for piping into an encoder
+  --every     only write every nth tick (default 1)
+  --scale     multiply the arena's size by this (default 1)
+  --paths     draw predicted dasher paths and hunter aims
+  --hitboxes  draw collision shapes";
+
+enum Output {
+    Directory(String),
+    Raw,
+}
+
+struct Options {
+    replay_path: String,
+    output: Output,
+    every: u32,
+    scale: u32,
+    overlays: Overlays,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut replay_path = None;
+    let mut output = Output::Directory(DEFAULT_OUTPUT_DIRECTORY.to_string());
+    let mut every = 1;
+    let mut scale = 1;
+    let mut overlays = Overlays {
+        paths: false,
+        hitboxes: false,
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut next_value = || args.next().ok_or(format!("{} needs a value", arg));
+        match arg.as_str() {
+            "--out" => output = Output::Directory(next_value()?.clone()),
+            "--raw" => output = Output::Raw,
+            "--every" | "--scale" => {
+                let value = next_value()?
+                    .parse::<u32>()
+                    .map_err(|error| format!("{}: {}", arg, error))?;
+                if value == 0 {
+                    return Err(format!("{} must be at least 1", arg));
+                }
+                if arg == "--every" {
+                    every = value;
+                } else {
+                    scale = value;
+                }
+            }
+            "--paths" => overlays.paths = true,
+            "--hitboxes" => overlays.hitboxes = true,
+            _ if replay_path.is_none() && !arg.starts_with("--") => {
+                replay_path = Some(arg.clone());
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(Options {
+        replay_path: replay_path.ok_or("no replay given")?,
+        output,
+        every,
+        scale,
+        overlays,
+    })
+}
+
+/* Plays a replay back with no window, rendering the starting state and then a frame after every nth tick.
+Progress and anything an encoder needs to know go to stderr, so stdout stays clean for raw frames. */
+pub fn render_replay(args: &[String]) {
+    let options = parse_options(args).unwrap_or_else(|error| {
+        eprintln!("{}\n{}", error, USAGE);
+        process::exit(1);
+    });
+    if let Err(error) = run(&options) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+fn run(options: &Options) -> Result<(), String> {
+    let replay = Replay::load(&options.replay_path)?;
+    let mut game = replay.start_game()?;
+    let width = GAME_SCREEN_WIDTH as u32 * options.scale;
+    let height = GAME_SCREEN_HEIGHT as u32 * options.scale;
+    match &options.output {
+        Output::Directory(directory) => {
+            fs::create_dir_all(directory)
+                .map_err(|error| format!("Could not create {}: {}", directory, error))?;
+        }
+        Output::Raw => {
+            eprintln!(
+                "Raw rgb24 frames, {}x{} at {} fps",
+                width,
+                height,
+                TICKS_PER_SECOND as f64 / options.every as f64
+            );
+        }
+    }
+    let camera = Camera::new();
+    let stdout = io::stdout();
+    let mut stdout = stdout.lock();
+    let mut frames = 0;
+    let mut inputs = replay.inputs.iter();
+    let mut tick: u32 = 0;
+    loop {
+        if tick.is_multiple_of(options.every) {
+            write_frame(&game, &camera, options, width, height, frames, &mut stdout)?;
+            frames += 1;
+        }
+        match inputs.next() {
+            Some(input) => game.step(input),
+            None => break,
+        }
+        tick += 1;
+    }
+    stdout
+        .flush()
+        .map_err(|error| format!("Could not write frame: {}", error))?;
+    eprintln!(
+        "Rendered {} frames from {} ticks",
+        frames,
+        replay.inputs.len()
+    );
+    Ok(())
+}
+
+fn write_frame(
+    game: &Game,
+    camera: &Camera,
+    options: &Options,
+    width: u32,
+    height: u32,
+    frame: u32,
+    stdout: &mut impl Write,
+) -> Result<(), String> {
+    let surface = render_offscreen(game, camera, &options.overlays, 1.0, width, height)?;
+    match &options.output {
+        Output::Directory(directory) => {
+            surface.save_bmp(format!("{}/frame-{:06}.bmp", directory, frame))?;
+        }
+        Output::Raw => {
+            let mut result = Ok(());
+            for_each_row(&surface, |row| {
+                if result.is_ok() {
+                    result = stdout.write_all(row);
+                }
+            });
+            result.map_err(|error| format!("Could not write frame: {}", error))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,264 @@
+use std::{fmt::Display, fs, str::FromStr};
+
+use crate::{
+    game::{Arena, Game, MovementModel, Player, Position, TickInput},
+    input::ControlMode,
+    settings::{parse_positive, parse_speed_multiplier},
+};
+
+pub const REPLAY_PATH: &str = "last.replay";
+
+// Bumped whenever the layout changes, so older replays can still be read the way they were written
+const REPLAY_VERSION: u32 = 2;
+// Flags in the order they're written for each tick, with '.' for any that aren't set
+const FLAG_CHARS: [char; 9] = ['u', 'd', 'l', 'r', 'f', 'w', 'D', 'b', 's'];
+// Version 1 replays were recorded before slow walk existed
+const VERSION_1_FLAG_CHARS: [char; 8] = ['u', 'd', 'l', 'r', 'f', 'D', 'b', 's'];
+
+// A seed plus every tick's input is enough to play a game back exactly
+pub struct Replay {
+    pub seed: u64,
+    pub control_mode: ControlMode,
+    pub level: Option<String>,
+    pub movement_model: MovementModel,
+    pub inputs: Vec<TickInput>,
+}
+
+impl Replay {
+    pub fn new(
+        seed: u64,
+        control_mode: ControlMode,
+        level: Option<String>,
+        movement_model: MovementModel,
+    ) -> Self {
+        Replay {
+            seed,
+            control_mode,
+            level,
+            movement_model,
+            inputs: Vec::new(),
+        }
+    }
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|error| format!("Could not read replay {}: {}", path, error))?;
+        Replay::parse(&text).map_err(|error| format!("Bad replay {}: {}", path, error))
+    }
+    // The game as it was when recording started, ready for the recorded inputs
+    pub fn start_game(&self) -> Result<Game, String> {
+        let mut game = Game::with_seed(self.seed);
+        if let Some(level) = self.level.as_ref() {
+            game.arena = Arena::load(level)?;
+        }
+        game.movement_model = self.movement_model;
+        Player::spawn(&mut game);
+        Ok(game)
+    }
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|error| format!("Could not write replay {}: {}", path, error))
+    }
+    /* A header of one setting per line, then one line per tick:
+    version <version>, missing from version 1 replays
+    seed <seed>
+    control_mode <mode>
+    level <path>
+    normalize_diagonals, acceleration, friction and slow_walk_speed as in the settings file, with the same limits
+    ticks
+    <flags> [analog <x> <y>] [target <x> <y>]
+    Floats are written in full, so they read back exactly. */
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut version = 1;
+        let mut seed = None;
+        let mut replay = Replay::new(0, ControlMode::Buttons, None, MovementModel::default());
+        let mut lines = text.lines().enumerate();
+        for (index, line) in lines.by_ref() {
+            let line_number = index + 1;
+            let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let model = &mut replay.movement_model;
+            match command {
+                "version" => {
+                    version = parse_value(rest, line_number)?;
+                    if version == 0 || version > REPLAY_VERSION {
+                        return Err(format!(
+                            "line {}: unknown replay version {}",
+                            line_number, version
+                        ));
+                    }
+                }
+                "seed" => seed = Some(parse_value(rest, line_number)?),
+                "control_mode" => {
+                    replay.control_mode = ControlMode::from_name(rest).ok_or(format!(
+                        "line {}: unknown control mode {}",
+                        line_number, rest
+                    ))?;
+                }
+                "level" => replay.level = Some(rest.to_string()),
+                "normalize_diagonals" => {
+                    model.normalize_diagonals = parse_value(rest, line_number)?;
+                }
+                "acceleration" => model.acceleration = parse_positive(rest, line_number)?,
+                "friction" => model.friction = parse_positive(rest, line_number)?,
+                "slow_walk_speed" => {
+                    model.slow_walk_speed = parse_speed_multiplier(rest, line_number)?;
+                }
+                "ticks" => break,
+                _ => {
+                    return Err(format!("line {}: unknown header {}", line_number, command));
+                }
+            }
+        }
+        replay.seed = seed.ok_or("no seed in the header")?;
+        let flag_chars: &[char] = if version == 1 {
+            // Recorded before the movement model, when diagonals weren't slowed down
+            replay.movement_model.normalize_diagonals = false;
+            &VERSION_1_FLAG_CHARS
+        } else {
+            &FLAG_CHARS
+        };
+        for (index, line) in lines {
+            let input = parse_tick(line, flag_chars)
+                .map_err(|error| format!("line {}: {}", index + 1, error))?;
+            replay.inputs.push(input);
+        }
+        Ok(replay)
+    }
+    fn to_text(&self) -> String {
+        let mut text = format!(
+            "version {}\nseed {}\ncontrol_mode {}\n",
+            REPLAY_VERSION,
+            self.seed,
+            self.control_mode.get_name()
+        );
+        if let Some(level) = self.level.as_ref() {
+            text += &format!("level {}\n", level);
+        }
+        let model = &self.movement_model;
+        text += &format!("normalize_diagonals {}\n", model.normalize_diagonals);
+        text += &format!("acceleration {}\n", model.acceleration);
+        text += &format!("friction {}\n", model.friction);
+        text += &format!("slow_walk_speed {}\n", model.slow_walk_speed);
+        text += "ticks\n";
+        for input in self.inputs.iter() {
+            let flags = [
+                input.up,
+                input.down,
+                input.left,
+                input.right,
+                input.focus,
+                input.slow_walk,
+                input.dodge,
+                input.bomb,
+                input.respawn,
+            ];
+            for (set, flag) in flags.iter().zip(FLAG_CHARS) {
+                text.push(if *set { flag } else { '.' });
+            }
+            if let Some((x, y)) = input.analog {
+                text += &format!(" analog {} {}", x, y);
+            }
+            if let Some(target) = input.target {
+                text += &format!(" target {} {}", target.x, target.y);
+            }
+            text.push('\n');
+        }
+        text
+    }
+}
+
+fn parse_value<T: FromStr>(text: &str, line_number: usize) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    text.parse::<T>()
+        .map_err(|error| format!("line {}: {}", line_number, error))
+}
+
+// Flags are looked up by letter, so a layout without one just leaves it unset
+fn parse_tick(line: &str, flag_chars: &[char]) -> Result<TickInput, String> {
+    let mut words = line.split_whitespace();
+    let flags: Vec<char> = words.next().ok_or("empty tick")?.chars().collect();
+    if flags.len() != flag_chars.len() {
+        return Err(format!(
+            "expected {} flags, got {}",
+            flag_chars.len(),
+            flags.iter().collect::<String>()
+        ));
+    }
+    let set = |flag: char| {
+        flag_chars
+            .iter()
+            .position(|found| *found == flag)
+            .is_some_and(|index| flags[index] == flag)
+    };
+    let mut input = TickInput {
+        up: set('u'),
+        down: set('d'),
+        left: set('l'),
+        right: set('r'),
+        focus: set('f'),
+        slow_walk: set('w'),
+        dodge: set('D'),
+        bomb: set('b'),
+        respawn: set('s'),
+        ..TickInput::default()
+    };
+    while let Some(word) = words.next() {
+        let mut next_number = || -> Result<f64, String> {
+            words
+                .next()
+                .ok_or(format!("{} needs two numbers", word))?
+                .parse::<f64>()
+                .map_err(|error| error.to_string())
+        };
+        let (x, y) = (next_number()?, next_number()?);
+        match word {
+            "analog" => input.analog = Some((x, y)),
+            "target" => input.target = Some(Position { x, y }),
+            _ => return Err(format!("unknown tick field {}", word)),
+        }
+    }
+    Ok(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_version_1_replays() {
+        let replay =
+            Replay::parse("seed 7\ncontrol_mode buttons\nticks\nu..r.D..\n........\n").unwrap();
+        assert_eq!(replay.seed, 7);
+        assert!(!replay.movement_model.normalize_diagonals);
+        assert_eq!(replay.inputs.len(), 2);
+        let input = &replay.inputs[0];
+        assert!(input.up && input.right && input.dodge);
+        assert!(!input.down && !input.left && !input.slow_walk && !input.bomb);
+    }
+
+    #[test]
+    fn reads_back_what_it_writes() {
+        let mut replay = Replay::new(3, ControlMode::Mouse, None, MovementModel::default());
+        replay.inputs.push(TickInput {
+            left: true,
+            slow_walk: true,
+            bomb: true,
+            analog: Some((0.1, -0.3)),
+            ..TickInput::default()
+        });
+        replay.inputs.push(TickInput {
+            target: Some(Position { x: 12.5, y: 300.25 }),
+            ..TickInput::default()
+        });
+        let parsed = Replay::parse(&replay.to_text()).unwrap();
+        assert_eq!(parsed.to_text(), replay.to_text());
+        assert!(parsed.movement_model.normalize_diagonals);
+        assert!(parsed.inputs[0].slow_walk && parsed.inputs[0].bomb);
+    }
+
+    #[test]
+    fn rejects_unknown_versions() {
+        assert!(Replay::parse("version 3\nseed 1\nticks\n").is_err());
+    }
+}
@@ -0,0 +1,239 @@
+use std::{collections::HashMap, fs, io::ErrorKind};
+
+use sdl2::{controller::Button, keyboard::Keycode};
+
+use crate::{
+    game::MovementModel,
+    input::{Action, Bindings, ControlMode},
+};
+
+pub const SETTINGS_PATH: &str = "settings.txt";
+const DEFAULT_CONTROLLER_DEADZONE: f64 = 0.2;
+
+pub struct Settings {
+    pub key_bindings: Bindings<Keycode>,
+    // Used by any controller without bindings of its own
+    pub button_bindings: Bindings<Button>,
+    // By the controller's name as SDL reports it
+    pub controller_button_bindings: HashMap<String, Bindings<Button>>,
+    // How far a stick must be pushed, from 0 to 1, before it moves the player
+    pub controller_deadzone: f64,
+    pub control_mode: ControlMode,
+    pub movement_model: MovementModel,
+    pub vsync: bool,
+    pub fullscreen: bool,
+    // Only scale the arena by whole numbers, leaving wider bars but keeping pixels square and sharp
+    pub integer_scaling: bool,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Settings {
+            key_bindings: Bindings::default(),
+            button_bindings: Bindings::default(),
+            controller_button_bindings: HashMap::new(),
+            controller_deadzone: DEFAULT_CONTROLLER_DEADZONE,
+            control_mode: ControlMode::Buttons,
+            movement_model: MovementModel::default(),
+            vsync: true,
+            fullscreen: false,
+            integer_scaling: false,
+        }
+    }
+    // A missing file just means nothing has been changed from the defaults yet
+    pub fn load(path: &str) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                Settings::parse(&text).map_err(|error| format!("Bad settings {}: {}", path, error))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Ok(Settings::new()),
+            Err(error) => Err(format!("Could not read settings {}: {}", path, error)),
+        }
+    }
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        fs::write(path, self.to_text())
+            .map_err(|error| format!("Could not write settings {}: {}", path, error))
+    }
+    /* One setting per line, blank lines and lines starting with # are ignored:
+    bind <action> <key name>
+    button <action> <button name> [controller name]
+    controller_deadzone <0 to 1>
+    control_mode buttons|mouse
+    normalize_diagonals true|false
+    acceleration|friction <speed change per tick, or inf>
+    slow_walk_speed <multiplier above 0, up to 1>
+    vsync true|false
+    fullscreen true|false
+    integer_scaling true|false
+    Any bind line replaces the default key bindings entirely, and any button line without a controller
+    name replaces the default button bindings. */
+    fn parse(text: &str) -> Result<Self, String> {
+        let mut settings = Settings::new();
+        let mut key_bindings = Bindings::empty();
+        let mut button_bindings = Bindings::empty();
+        let mut found_key_bindings = false;
+        let mut found_button_bindings = false;
+        for (index, line) in text.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.trim();
+            let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let rest = rest.trim();
+            match command {
+                "" => {}
+                _ if command.starts_with('#') => {}
+                "bind" => {
+                    // Key names can contain spaces, like "Left Shift"
+                    let (action_name, key_name) = rest.split_once(' ').ok_or(format!(
+                        "line {}: bind needs an action and a key",
+                        line_number
+                    ))?;
+                    let action = parse_action(action_name, line_number)?;
+                    let keycode = Keycode::from_name(key_name.trim())
+                        .ok_or(format!("line {}: unknown key {}", line_number, key_name))?;
+                    key_bindings.bind(keycode, action);
+                    found_key_bindings = true;
+                }
+                "button" => {
+                    let mut words = rest.splitn(3, ' ');
+                    let (action_name, button_name) = match (words.next(), words.next()) {
+                        (Some(action_name), Some(button_name)) => (action_name, button_name),
+                        _ => {
+                            return Err(format!(
+                                "line {}: button needs an action and a button",
+                                line_number
+                            ))
+                        }
+                    };
+                    let action = parse_action(action_name, line_number)?;
+                    let button = Button::from_string(button_name).ok_or(format!(
+                        "line {}: unknown button {}",
+                        line_number, button_name
+                    ))?;
+                    match words.next() {
+                        Some(controller_name) => {
+                            settings
+                                .controller_button_bindings
+                                .entry(controller_name.trim().to_string())
+                                .or_insert_with(Bindings::empty)
+                                .bind(button, action);
+                        }
+                        None => {
+                            button_bindings.bind(button, action);
+                            found_button_bindings = true;
+                        }
+                    }
+                }
+                "controller_deadzone" => {
+                    let deadzone = rest
+                        .parse::<f64>()
+                        .map_err(|error| format!("line {}: {}", line_number, error))?;
+                    if !(0.0..1.0).contains(&deadzone) {
+                        return Err(format!(
+                            "line {}: controller_deadzone must be from 0 up to 1",
+                            line_number
+                        ));
+                    }
+                    settings.controller_deadzone = deadzone;
+                }
+                "control_mode" => {
+                    settings.control_mode = ControlMode::from_name(rest).ok_or(format!(
+                        "line {}: control_mode must be buttons or mouse",
+                        line_number
+                    ))?;
+                }
+                "normalize_diagonals" => {
+                    settings.movement_model.normalize_diagonals = parse_bool(rest, line_number)?;
+                }
+                "acceleration" => {
+                    settings.movement_model.acceleration = parse_positive(rest, line_number)?;
+                }
+                "friction" => {
+                    settings.movement_model.friction = parse_positive(rest, line_number)?;
+                }
+                "slow_walk_speed" => {
+                    settings.movement_model.slow_walk_speed =
+                        parse_speed_multiplier(rest, line_number)?;
+                }
+                "vsync" => settings.vsync = parse_bool(rest, line_number)?,
+                "fullscreen" => settings.fullscreen = parse_bool(rest, line_number)?,
+                "integer_scaling" => settings.integer_scaling = parse_bool(rest, line_number)?,
+                _ => {
+                    return Err(format!("line {}: unknown setting {}", line_number, command));
+                }
+            }
+        }
+        if found_key_bindings {
+            settings.key_bindings = key_bindings;
+        }
+        if found_button_bindings {
+            settings.button_bindings = button_bindings;
+        }
+        Ok(settings)
+    }
+    fn to_text(&self) -> String {
+        let mut text = String::new();
+        for (keycode, action) in self.key_bindings.iter() {
+            text += &format!("bind {} {}\n", action.get_name(), keycode.name());
+        }
+        for (button, action) in self.button_bindings.iter() {
+            text += &format!("button {} {}\n", action.get_name(), button.string());
+        }
+        for (controller_name, bindings) in self.controller_button_bindings.iter() {
+            for (button, action) in bindings.iter() {
+                text += &format!(
+                    "button {} {} {}\n",
+                    action.get_name(),
+                    button.string(),
+                    controller_name
+                );
+            }
+        }
+        text += &format!("controller_deadzone {}\n", self.controller_deadzone);
+        text += &format!("control_mode {}\n", self.control_mode.get_name());
+        let model = &self.movement_model;
+        text += &format!("normalize_diagonals {}\n", model.normalize_diagonals);
+        text += &format!("acceleration {}\n", model.acceleration);
+        text += &format!("friction {}\n", model.friction);
+        text += &format!("slow_walk_speed {}\n", model.slow_walk_speed);
+        text += &format!("vsync {}\n", self.vsync);
+        text += &format!("fullscreen {}\n", self.fullscreen);
+        text += &format!("integer_scaling {}\n", self.integer_scaling);
+        text
+    }
+}
+
+fn parse_action(name: &str, line_number: usize) -> Result<Action, String> {
+    Action::from_name(name).ok_or(format!("line {}: unknown action {}", line_number, name))
+}
+
+// Accepts "inf" too, for changes that should happen all at once
+pub fn parse_positive(text: &str, line_number: usize) -> Result<f64, String> {
+    let value = text
+        .parse::<f64>()
+        .map_err(|error| format!("line {}: {}", line_number, error))?;
+    if value > 0.0 {
+        Ok(value)
+    } else {
+        Err(format!("line {}: {} must be above 0", line_number, text))
+    }
+}
+
+// A fraction of full speed, so it can't be infinite and can't speed the player up
+pub fn parse_speed_multiplier(text: &str, line_number: usize) -> Result<f64, String> {
+    let value = text
+        .parse::<f64>()
+        .map_err(|error| format!("line {}: {}", line_number, error))?;
+    if value > 0.0 && value <= 1.0 {
+        Ok(value)
+    } else {
+        Err(format!(
+            "line {}: {} must be above 0 and at most 1",
+            line_number, text
+        ))
+    }
+}
+
+fn parse_bool(text: &str, line_number: usize) -> Result<bool, String> {
+    text.parse::<bool>()
+        .map_err(|error| format!("line {}: {}", line_number, error))
+}